@@ -1,33 +1,33 @@
 # Print an optspec for argparse to handle cmd's options that are independent of any subcommand.
 function __fish_roxide_global_optspecs
-	string join \n r/recursive l/list i/interactive= p/pattern= f/force= v/verbose d/dir h/help V/version
+    string join \n r/recursive l/list i/interactive= p/pattern= f/force= v/verbose d/dir h/help V/version
 end
 
 function __fish_roxide_needs_command
-	# Figure out if the current invocation already has a command.
-	set -l cmd (commandline -opc)
-	set -e cmd[1]
-	argparse -s (__fish_roxide_global_optspecs) -- $cmd 2>/dev/null
-	or return
-	if set -q argv[1]
-		# Also print the command, so this can be used to figure out what it is.
-		echo $argv[1]
-		return 1
-	end
-	return 0
+    # Figure out if the current invocation already has a command.
+    set -l cmd (commandline -opc)
+    set -e cmd[1]
+    argparse -s (__fish_roxide_global_optspecs) -- $cmd 2>/dev/null
+    or return
+    if set -q argv[1]
+        # Also print the command, so this can be used to figure out what it is.
+        echo $argv[1]
+        return 1
+    end
+    return 0
 end
 
 function __fish_roxide_using_subcommand
-	set -l cmd (__fish_roxide_needs_command)
-	test -z "$cmd"
-	and return 1
-	contains -- $cmd[1] $argv
+    set -l cmd (__fish_roxide_needs_command)
+    test -z "$cmd"
+    and return 1
+    contains -- $cmd[1] $argv
 end
 
 complete -c roxide -n "__fish_roxide_needs_command" -s i -l interactive -d 'whether to prompt before removals' -r -f -a "never\t'Never prompt'
 once\t'Prompt once before removing more than three files or when removing recursivly'
 always\t'Prompt before every removal'"
-complete -c roxide -n "__fish_roxide_needs_command" -s p -l pattern -d 'remove files matching the pattern. revert will not work on patterns, provide -rp for recursive remove' -r
+complete -c roxide -n "__fish_roxide_needs_command" -s p -l pattern -d 'remove files matching the pattern, provide -rp for recursive remove' -r
 complete -c roxide -n "__fish_roxide_needs_command" -s f -l force -d 'Forces deletion without moving files to the trash directory' -r -F
 complete -c roxide -n "__fish_roxide_needs_command" -s r -l recursive -d 'Remove directories and their contents recursively'
 complete -c roxide -n "__fish_roxide_needs_command" -s l -l list -d 'list items which will be affected, (dry run)'
@@ -35,8 +35,114 @@ complete -c roxide -n "__fish_roxide_needs_command" -s v -l verbose -d 'Enable v
 complete -c roxide -n "__fish_roxide_needs_command" -s d -l dir -d 'remove empty directories'
 complete -c roxide -n "__fish_roxide_needs_command" -s h -l help -d 'Print help (see more with \'--help\')'
 complete -c roxide -n "__fish_roxide_needs_command" -s V -l version -d 'Print version'
-complete -c roxide -n "__fish_roxide_needs_command" -a "revert" -d 'revert the previous remove'
+complete -c roxide -n "__fish_roxide_needs_command" -a "revert" -d 'revert the previous remove, every item removed by it is restored'
+complete -c roxide -n "__fish_roxide_needs_command" -a "redo" -d 'trash the items of the last reverted remove again'
+complete -c roxide -n "__fish_roxide_needs_command" -a "restore" -d 'restore trashed items by their original path'
+complete -c roxide -n "__fish_roxide_needs_command" -a "history" -d 'list the operations and items in history'
+complete -c roxide -n "__fish_roxide_needs_command" -a "list" -d 'list the items in the trash with their original locations'
+complete -c roxide -n "__fish_roxide_needs_command" -a "du" -d 'show what takes space in the trash, by original directory, extension, age and operation'
+complete -c roxide -n "__fish_roxide_needs_command" -a "find" -d 'search the trashed items, and the files inside trashed directories'
+complete -c roxide -n "__fish_roxide_needs_command" -a "cat" -d 'print trashed files without restoring them'
+complete -c roxide -n "__fish_roxide_needs_command" -a "diff" -d 'show what changed between a trashed file and the file now at its original path, or another trashed version of it'
+complete -c roxide -n "__fish_roxide_needs_command" -a "ls" -d 'list the contents of a trashed directory without restoring it'
+complete -c roxide -n "__fish_roxide_needs_command" -a "tui" -d 'browse the trash in a full screen interface, to search, preview, restore and purge items'
+complete -c roxide -n "__fish_roxide_needs_command" -a "purge" -d 'permanently delete trashed items'
+complete -c roxide -n "__fish_roxide_needs_command" -a "pin" -d 'keep trashed items, `gc` and `max_trash_size` never remove pinned items'
+complete -c roxide -n "__fish_roxide_needs_command" -a "unpin" -d 'let `gc` and `max_trash_size` remove trashed items again'
+complete -c roxide -n "__fish_roxide_needs_command" -a "gc" -d 'permanently remove the items trashed before AGE from the trash, and the oldest items of trash dirs over `max_trash_size`'
 complete -c roxide -n "__fish_roxide_needs_command" -a "help" -d 'Print this message or the help of the given subcommand(s)'
-complete -c roxide -n "__fish_roxide_using_subcommand revert" -s h -l help -d 'Print help'
-complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert help" -f -a "revert" -d 'revert the previous remove'
-complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c roxide -n "__fish_roxide_using_subcommand revert" -l op -d 'revert the remove with this operation id instead of the previous one' -r
+complete -c roxide -n "__fish_roxide_using_subcommand revert" -l on-conflict -d 'what to do if the original path exists again' -r -f -a "rename\t'Restore next to the existing item, eg: `notes.restored.txt`'
+overwrite\t'Replace the existing item'
+skip\t'Leave the item in the trash'
+ask\t'Ask what to do for every conflict'
+merge\t'Merge a trashed directory into the existing directory, conflicting entries are renamed'"
+complete -c roxide -n "__fish_roxide_using_subcommand revert" -l to -d 'restore into this directory instead, keeping the layout of the items, or as this path if it is a single item' -r -F
+complete -c roxide -n "__fish_roxide_using_subcommand revert" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c roxide -n "__fish_roxide_using_subcommand redo" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand restore" -l on-conflict -d 'what to do if the original path exists again' -r -f -a "rename\t'Restore next to the existing item, eg: `notes.restored.txt`'
+overwrite\t'Replace the existing item'
+skip\t'Leave the item in the trash'
+ask\t'Ask what to do for every conflict'
+merge\t'Merge a trashed directory into the existing directory, conflicting entries are renamed'"
+complete -c roxide -n "__fish_roxide_using_subcommand restore" -l to -d 'restore into this directory instead, keeping the layout of the items, or as this path if it is a single item' -r -F
+complete -c roxide -n "__fish_roxide_using_subcommand restore" -l newest -d 'if a path was trashed more than once, restore the newest without asking'
+complete -c roxide -n "__fish_roxide_using_subcommand restore" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c roxide -n "__fish_roxide_using_subcommand history" -l since -d 'only items trashed at or after TIME, eg: 2024-11-12, "2024-11-12 21:44" or 2d (ago)' -r
+complete -c roxide -n "__fish_roxide_using_subcommand history" -l until -d 'only items trashed at or before TIME' -r
+complete -c roxide -n "__fish_roxide_using_subcommand history" -l dir -d 'only items whose original path is inside DIR' -r -F
+complete -c roxide -n "__fish_roxide_using_subcommand history" -l glob -d 'only items whose original path matches GLOB' -r
+complete -c roxide -n "__fish_roxide_using_subcommand history" -l op -d 'only items of the operation ID, or of the operation whose id starts with ID' -r
+complete -c roxide -n "__fish_roxide_using_subcommand history" -l status -d 'only items with this status' -r -f -a "trashed\t'The item is in the trash dir'
+reverted\t'The item was brought back by `revert`, `redo` trashes it again'
+restored\t'The item was brought back by `restore`'
+purged\t'The item was removed from the trash dir for good'"
+complete -c roxide -n "__fish_roxide_using_subcommand history" -l format -r -f -a "table\t'Aligned columns for reading'
+json\t'A JSON array of objects'
+csv\t'Comma separated values with a header row'"
+complete -c roxide -n "__fish_roxide_using_subcommand history" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c roxide -n "__fish_roxide_using_subcommand list" -l sort -d 'sort the items by' -r -f -a "time\t'Deletion time, oldest first'
+size\t'Size, smallest first'
+name\t'Name in the trash dir'"
+complete -c roxide -n "__fish_roxide_using_subcommand list" -l dir -d 'only items whose original path is inside DIR' -r -F
+complete -c roxide -n "__fish_roxide_using_subcommand list" -l glob -d 'only items whose original path matches GLOB' -r
+complete -c roxide -n "__fish_roxide_using_subcommand list" -l format -r -f -a "table\t'Aligned columns for reading'
+json\t'A JSON array of objects'
+csv\t'Comma separated values with a header row'"
+complete -c roxide -n "__fish_roxide_using_subcommand list" -s r -l reverse -d 'reverse the order'
+complete -c roxide -n "__fish_roxide_using_subcommand list" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c roxide -n "__fish_roxide_using_subcommand du" -l top -d 'how many of the largest items to show' -r
+complete -c roxide -n "__fish_roxide_using_subcommand du" -l depth -d 'how many directories below $HOME, or /, make up the directory an item is counted in' -r
+complete -c roxide -n "__fish_roxide_using_subcommand du" -l format -r -f -a "table\t'Aligned columns for reading'
+json\t'A JSON array of objects'
+csv\t'Comma separated values with a header row'"
+complete -c roxide -n "__fish_roxide_using_subcommand du" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l name -d 'only files whose name matches GLOB, eg: \'*.rs\'' -r
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l regex -d 'only files whose name matches the regular expression REGEX' -r
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l dir -d 'only files whose original path is inside DIR' -r -F
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l since -d 'only items trashed at or after TIME, eg: 2024-11-12, "2024-11-12 21:44" or 2d (ago)' -r
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l until -d 'only items trashed at or before TIME' -r
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l min-size -d 'only files of at least SIZE, eg: 10K or 2MiB' -r
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l max-size -d 'only files of at most SIZE' -r
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l contains -d 'only files with a line matching the regular expression REGEX' -r
+complete -c roxide -n "__fish_roxide_using_subcommand find" -l format -r -f -a "table\t'Aligned columns for reading'
+json\t'A JSON array of objects'
+csv\t'Comma separated values with a header row'"
+complete -c roxide -n "__fish_roxide_using_subcommand find" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c roxide -n "__fish_roxide_using_subcommand cat" -l op -d 'the version trashed by the operation with this id, instead of the newest' -r
+complete -c roxide -n "__fish_roxide_using_subcommand cat" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand diff" -l op -d 'the version trashed by the operation with this id, instead of the newest' -r
+complete -c roxide -n "__fish_roxide_using_subcommand diff" -l against -d 'compare with the version trashed by the operation with this id, instead of the file' -r
+complete -c roxide -n "__fish_roxide_using_subcommand diff" -s U -l context -d 'lines of context around the changes' -r
+complete -c roxide -n "__fish_roxide_using_subcommand diff" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand ls" -l op -d 'the version trashed by the operation with this id, instead of the newest' -r
+complete -c roxide -n "__fish_roxide_using_subcommand ls" -s R -l recursive -d 'list the sub directories too'
+complete -c roxide -n "__fish_roxide_using_subcommand ls" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand tui" -l on-conflict -d 'what to do if the original path of a restored item exists again' -r -f -a "rename\t'Restore next to the existing item, eg: `notes.restored.txt`'
+overwrite\t'Replace the existing item'
+skip\t'Leave the item in the trash'
+ask\t'Ask what to do for every conflict'
+merge\t'Merge a trashed directory into the existing directory, conflicting entries are renamed'"
+complete -c roxide -n "__fish_roxide_using_subcommand tui" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c roxide -n "__fish_roxide_using_subcommand purge" -l op -d 'the items trashed by the operation with this id' -r
+complete -c roxide -n "__fish_roxide_using_subcommand purge" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand pin" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand unpin" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand gc" -l dry-run -d 'only list what would be removed'
+complete -c roxide -n "__fish_roxide_using_subcommand gc" -s h -l help -d 'Print help'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "revert" -d 'revert the previous remove, every item removed by it is restored'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "redo" -d 'trash the items of the last reverted remove again'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "restore" -d 'restore trashed items by their original path'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "history" -d 'list the operations and items in history'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "list" -d 'list the items in the trash with their original locations'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "du" -d 'show what takes space in the trash, by original directory, extension, age and operation'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "find" -d 'search the trashed items, and the files inside trashed directories'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "cat" -d 'print trashed files without restoring them'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "diff" -d 'show what changed between a trashed file and the file now at its original path, or another trashed version of it'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "ls" -d 'list the contents of a trashed directory without restoring it'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "tui" -d 'browse the trash in a full screen interface, to search, preview, restore and purge items'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "purge" -d 'permanently delete trashed items'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "pin" -d 'keep trashed items, `gc` and `max_trash_size` never remove pinned items'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "unpin" -d 'let `gc` and `max_trash_size` remove trashed items again'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "gc" -d 'permanently remove the items trashed before AGE from the trash, and the oldest items of trash dirs over `max_trash_size`'
+complete -c roxide -n "__fish_roxide_using_subcommand help; and not __fish_seen_subcommand_from revert redo restore history list du find cat diff ls tui purge pin unpin gc help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
 
 use std::path::PathBuf;
 
@@ -34,7 +35,7 @@ pub struct Cli {
     #[arg(short, long)]
     pub interactive: Option<InteractiveMode>,
 
-    /// remove files matching the pattern. revert will not work on patterns, provide -rp for recursive remove
+    /// remove files matching the pattern, provide -rp for recursive remove
     #[arg(short = 'p', long = "pattern", value_name = "PATTERN")] // roxide some/dir -p .pdf
     pub pattern: Option<String>, // Accept the file-matching pattern
 
@@ -63,8 +64,219 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    /// revert the previous remove
-    Revert,
+    /// revert the previous remove, every item removed by it is restored
+    Revert {
+        /// revert the remove with this operation id instead of the previous one
+        #[arg(long, value_name = "ID")]
+        op: Option<String>,
+        /// what to do if the original path exists again
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: ConflictPolicy,
+        /// restore into this directory instead, keeping the layout of the items, or as this path
+        /// if it is a single item
+        #[arg(long, value_name = "DIR|PATH")]
+        to: Option<PathBuf>,
+    },
+    /// trash the items of the last reverted remove again
+    Redo,
+    /// restore trashed items by their original path
+    Restore {
+        /// original path of the item, or a glob pattern matching original paths
+        #[arg(required = true, value_name = "PATH|GLOB")]
+        targets: Vec<String>,
+        /// if a path was trashed more than once, restore the newest without asking
+        #[arg(long)]
+        newest: bool,
+        /// what to do if the original path exists again
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: ConflictPolicy,
+        /// restore into this directory instead, keeping the layout of the items, or as this path
+        /// if it is a single item
+        #[arg(long, value_name = "DIR|PATH")]
+        to: Option<PathBuf>,
+    },
+    /// list the operations and items in history
+    History(HistoryArgs),
+    /// list the items in the trash with their original locations
+    List(ListArgs),
+    /// show what takes space in the trash, by original directory, extension, age and operation
+    Du(DuArgs),
+    /// search the trashed items, and the files inside trashed directories
+    Find(FindArgs),
+    /// print trashed files without restoring them
+    Cat {
+        /// original path of the file, or the name of the trashed item in the trash dir
+        #[arg(required = true, value_name = "PATH|NAME")]
+        targets: Vec<String>,
+        /// the version trashed by the operation with this id, instead of the newest
+        #[arg(long, value_name = "ID")]
+        op: Option<String>,
+    },
+    /// show what changed between a trashed file and the file now at its original path,
+    /// or another trashed version of it
+    Diff {
+        /// original path of the file, or the name of the trashed item in the trash dir
+        #[arg(value_name = "PATH|NAME")]
+        target: String,
+        /// the version trashed by the operation with this id, instead of the newest
+        #[arg(long, value_name = "ID")]
+        op: Option<String>,
+        /// compare with the version trashed by the operation with this id, instead of the file
+        #[arg(long, value_name = "ID")]
+        against: Option<String>,
+        /// lines of context around the changes
+        #[arg(long, short = 'U', default_value_t = 3, value_name = "LINES")]
+        context: usize,
+    },
+    /// list the contents of a trashed directory without restoring it
+    Ls {
+        /// original path of the directory, or the name of the trashed item in the trash dir
+        #[arg(value_name = "PATH|NAME")]
+        target: String,
+        /// the version trashed by the operation with this id, instead of the newest
+        #[arg(long, value_name = "ID")]
+        op: Option<String>,
+        /// list the sub directories too
+        #[arg(long, short = 'R')]
+        recursive: bool,
+    },
+    /// browse the trash in a full screen interface, to search, preview, restore and purge items
+    Tui {
+        /// what to do if the original path of a restored item exists again
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: ConflictPolicy,
+    },
+    /// permanently delete trashed items
+    Purge {
+        /// original path of the item, a glob pattern matching original paths or its name in the trash dir
+        #[arg(required_unless_present = "op", value_name = "PATH|GLOB|NAME")]
+        targets: Vec<String>,
+        /// the items trashed by the operation with this id
+        #[arg(long, value_name = "ID")]
+        op: Option<String>,
+    },
+    /// keep trashed items, `gc` and `max_trash_size` never remove pinned items
+    Pin {
+        /// original path of the item, or a glob pattern matching original paths
+        #[arg(required = true, value_name = "PATH|GLOB")]
+        targets: Vec<String>,
+    },
+    /// let `gc` and `max_trash_size` remove trashed items again
+    Unpin {
+        /// original path of the item, or a glob pattern matching original paths
+        #[arg(required = true, value_name = "PATH|GLOB")]
+        targets: Vec<String>,
+    },
+    /// permanently remove the items trashed before AGE from the trash,
+    /// and the oldest items of trash dirs over `max_trash_size`
+    Gc {
+        /// eg: 30d, 2w or 2024-11-12
+        #[arg(value_name = "AGE")]
+        older_than: Option<String>,
+        /// only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Filters and output format of `roxide history`
+#[derive(Args, Default, Debug)]
+pub struct HistoryArgs {
+    /// only items trashed at or after TIME, eg: 2024-11-12, "2024-11-12 21:44" or 2d (ago)
+    #[arg(long, value_name = "TIME")]
+    pub since: Option<String>,
+    /// only items trashed at or before TIME
+    #[arg(long, value_name = "TIME")]
+    pub until: Option<String>,
+    /// only items whose original path is inside DIR
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+    /// only items whose original path matches GLOB
+    #[arg(long, value_name = "GLOB")]
+    pub glob: Option<String>,
+    /// only items of the operation ID, or of the operation whose id starts with ID
+    #[arg(long, value_name = "ID")]
+    pub op: Option<String>,
+    /// only items with this status
+    #[arg(long, value_enum)]
+    pub status: Option<LogStatus>,
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+/// Sorting, filters and output format of `roxide list`
+#[derive(Args, Default, Debug)]
+pub struct ListArgs {
+    /// sort the items by
+    #[arg(long, value_enum, default_value_t)]
+    pub sort: SortKey,
+    /// reverse the order
+    #[arg(long, short)]
+    pub reverse: bool,
+    /// only items whose original path is inside DIR
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+    /// only items whose original path matches GLOB
+    #[arg(long, value_name = "GLOB")]
+    pub glob: Option<String>,
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+/// Breakdowns and output format of `roxide du`
+#[derive(Args, Debug)]
+pub struct DuArgs {
+    /// how many of the largest items to show
+    #[arg(long, default_value_t = 10, value_name = "N")]
+    pub top: usize,
+    /// how many directories below $HOME, or /, make up the directory an item is counted in
+    #[arg(long, default_value_t = 1, value_name = "N")]
+    pub depth: usize,
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+/// Filters and output format of `roxide find`
+#[derive(Args, Default, Debug)]
+pub struct FindArgs {
+    /// only files whose name matches GLOB, eg: '*.rs'
+    #[arg(long, value_name = "GLOB")]
+    pub name: Option<String>,
+    /// only files whose name matches the regular expression REGEX
+    #[arg(long, value_name = "REGEX")]
+    pub regex: Option<String>,
+    /// only files whose original path is inside DIR
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+    /// only items trashed at or after TIME, eg: 2024-11-12, "2024-11-12 21:44" or 2d (ago)
+    #[arg(long, value_name = "TIME")]
+    pub since: Option<String>,
+    /// only items trashed at or before TIME
+    #[arg(long, value_name = "TIME")]
+    pub until: Option<String>,
+    /// only files of at least SIZE, eg: 10K or 2MiB
+    #[arg(long, value_name = "SIZE")]
+    pub min_size: Option<String>,
+    /// only files of at most SIZE
+    #[arg(long, value_name = "SIZE")]
+    pub max_size: Option<String>,
+    /// only files with a line matching the regular expression REGEX
+    #[arg(long, value_name = "REGEX")]
+    pub contains: Option<String>,
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+/// Enum, determining the order of `roxide list`
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Default, Debug)]
+pub enum SortKey {
+    /// Deletion time, oldest first
+    #[default]
+    Time,
+    /// Size, smallest first
+    Size,
+    /// Name in the trash dir
+    Name,
 }
 
 /// Enum, determining when the `rm` will prompt the user about the file deletion
@@ -82,3 +294,46 @@ pub enum InteractiveMode {
     /// Prompt only on write-protected files
     PromptProtected,
 }
+
+/// Enum, determining what happens when an item is restored to a path which exists again
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Default, Debug)]
+pub enum ConflictPolicy {
+    /// Restore next to the existing item, eg: `notes.restored.txt`
+    #[default]
+    Rename,
+    /// Replace the existing item
+    Overwrite,
+    /// Leave the item in the trash
+    Skip,
+    /// Ask what to do for every conflict
+    Ask,
+    /// Merge a trashed directory into the existing directory, conflicting entries are renamed
+    Merge,
+}
+
+/// Enum, determining how listings are printed
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Default, Debug)]
+pub enum OutputFormat {
+    /// Aligned columns for reading
+    #[default]
+    Table,
+    /// A JSON array of objects
+    Json,
+    /// Comma separated values with a header row
+    Csv,
+}
+
+/// State of the item of a history record.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStatus {
+    /// The item is in the trash dir
+    #[default]
+    Trashed,
+    /// The item was brought back by `revert`, `redo` trashes it again
+    Reverted,
+    /// The item was brought back by `restore`
+    Restored,
+    /// The item was removed from the trash dir for good
+    Purged,
+}
@@ -1,12 +1,11 @@
-use std::fs::write;
-use std::fs::{self, create_dir_all};
+use std::fs;
 use std::os::unix::fs::MetadataExt;
 use std::path::Path;
+use std::sync::OnceLock;
 
 use anyhow::{Error, Result};
-use dirs::cache_dir;
 
-use crate::RoxError;
+use crate::{RoxError, TrashDir};
 
 /// This function checks if an item is located on a different device than the trash directory.
 ///
@@ -16,17 +15,18 @@ use crate::RoxError;
 /// source and target are on the same device. If the source and target are on different
 /// devices, the move operation would fail, as it cannot change the pointer across devices.
 ///
-/// In this case, to avoid panic the function will create a temporary file in
-/// users local cache directory and checks if the item given as argument and the temporary file
-/// are on the same device with its metadata.
-/// If not, it returns an error indicating that the operation would cross devices, which would
-/// lead to a failure when attempting to move the file.
+/// In this case, to avoid panic the function compares the device of the item with the device
+/// of the users home trash directory (`$HOME/.local/share/Trash/files`), which is created if
+/// it doesn't exist yet. The device of the trash directory is only looked up once per invocation.
+///
+/// The item itself is not followed if it is a symlink, so dangling symlinks can be checked too.
+/// If the metadata of the item can't be read, the device of its parent directory is used.
 ///
 /// # Arguments
 /// - `item`: The path of the item to check for cross-device movement.
 ///
 /// # Returns
-/// Returns `Ok(())` if the item and the temporary test file are on the same device,
+/// Returns `Ok(())` if the item and the trash directory are on the same device,
 /// or an error indicating a cross-device move attempt if they are not.
 ///
 /// # Example
@@ -41,25 +41,32 @@ use crate::RoxError;
 /// }
 /// ```
 pub fn check_cross_device(item: &Path) -> Result<(), Error> {
-    let item_metadata = fs::metadata(item)?.dev();
-
-    let cache_dir = cache_dir()
-        .ok_or(RoxError::CantFindCacheDir)?
-        .join("roxide");
+    let item_device = fs::symlink_metadata(item)
+        .or_else(|err| match item.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => fs::metadata(parent),
+            _ => Err(err),
+        })?
+        .dev();
 
-    create_dir_all(&cache_dir)?;
-    write(
-        cache_dir.join("state.txt"),
-        "Just a file to check CrossesDevices Error.",
-    )?;
-    let file_in_device = cache_dir.join("state.txt").metadata()?.dev();
     // check if the devices are different
-    if item_metadata != file_in_device {
+    if item_device != trash_device()? {
         return Err(RoxError::CrossesDevices(item.to_path_buf()).into());
     }
     Ok(())
 }
 
+/// Device of the home trash directory, looked up once per invocation.
+fn trash_device() -> Result<u64, Error> {
+    static TRASH_DEVICE: OnceLock<u64> = OnceLock::new();
+    if let Some(device) = TRASH_DEVICE.get() {
+        return Ok(*device);
+    }
+    let trash = TrashDir::home()?;
+    trash.create()?;
+    let device = fs::metadata(trash.files())?.dev();
+    Ok(*TRASH_DEVICE.get_or_init(|| device))
+}
+
 /// Checks if the current process is running as the root user.
 ///
 /// This function reads the `/proc/self/status` file, which contains information
@@ -82,21 +89,26 @@ pub fn check_cross_device(item: &Path) -> Result<(), Error> {
 /// }
 /// ```
 pub fn check_root() -> bool {
-    if let Ok(status) = fs::read_to_string("/proc/self/status") {
-        for line in status.lines() {
-            if line.starts_with("Uid:") {
-                let uid = line.split_whitespace().nth(1);
-                return uid == Some("0");
-            }
-        }
-    }
-    false
+    current_uid() == Some(0)
+}
+
+/// Returns the real User ID (UID) of the current process.
+///
+/// Read from the "Uid:" line of `/proc/self/status`, see [`check_root`].
+pub fn current_uid() -> Option<u32> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("Uid:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|uid| uid.parse().ok())
 }
 
 #[cfg(test)]
 #[allow(clippy::unwrap_used)]
 mod test {
     use std::fs::{create_dir_all, remove_dir_all};
+    use std::os::unix::fs::symlink;
     use std::path;
 
     use super::{check_cross_device, check_root};
@@ -119,6 +131,16 @@ mod test {
         remove_dir_all("trash/tests/check_cross_device_test").unwrap();
     }
 
+    #[test]
+    fn check_cross_device_dangling_symlink() {
+        create_dir_all("trash/tests/check_cross_device_dangling_symlink").unwrap();
+        let link = path::Path::new("trash/tests/check_cross_device_dangling_symlink/link");
+        symlink("does/not/exist", link).unwrap();
+        check_cross_device(link).unwrap();
+        check_cross_device(&link.with_file_name("missing")).unwrap();
+        remove_dir_all("trash/tests/check_cross_device_dangling_symlink").unwrap();
+    }
+
     #[test]
     #[cfg(target_os = "linux")]
     fn check_root_test() {
@@ -1,6 +1,10 @@
+use std::fmt;
 use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
 
-use anyhow::{Error, Result};
+use anyhow::{anyhow, Error, Result};
 use dirs::config_dir;
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +17,94 @@ pub struct Config {
 pub struct Settings {
     pub check_sha256: Option<bool>,
     pub new_check_sha256: bool,
+    /// Purge items trashed longer ago than this after removing something, eg: "30d"
+    pub gc_after: Option<String>,
+    /// Run that at most once in this period, "1d" if unset
+    pub gc_interval: Option<String>,
+    /// Maximum size of each trash dir, eg: "20GiB" or "10%" of its filesystem, see [`TrashQuota`]
+    pub max_trash_size: Option<String>,
+}
+
+impl Settings {
+    /// The parsed `max_trash_size`, `None` if it isn't set.
+    pub fn quota(&self) -> Result<Option<TrashQuota>> {
+        self.max_trash_size
+            .as_deref()
+            .map(TrashQuota::from_str)
+            .transpose()
+    }
+}
+
+/// # TrashQuota maximum size of a trash dir
+///
+/// Either a size in bytes, see [`parse_size`], or a percentage of the size of the filesystem
+/// the trash dir is on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrashQuota {
+    Bytes(u64),
+    Percent(f64),
+}
+
+impl TrashQuota {
+    /// The limit in bytes for the trash dir at `path`.
+    pub fn limit_for(&self, path: &Path) -> io::Result<u64> {
+        match self {
+            TrashQuota::Bytes(bytes) => Ok(*bytes),
+            TrashQuota::Percent(percent) => {
+                Ok((crate::filesystem_size(path)? as f64 * percent / 100.0) as u64)
+            }
+        }
+    }
+}
+
+impl FromStr for TrashQuota {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!("invalid trash size `{}`, expected eg: 20GiB, 500M or 10%", s);
+        let s = s.trim();
+        if let Some(percent) = s.strip_suffix('%') {
+            return match percent.trim().parse::<f64>() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(TrashQuota::Percent(percent)),
+                _ => Err(invalid()),
+            };
+        }
+        parse_size(s).map(TrashQuota::Bytes).map_err(|_| invalid())
+    }
+}
+
+/// Parses a size in bytes with an optional unit, `K`, `M`, `G` and `T` (or `KiB`, ...) are powers
+/// of 1024 and `KB`, `MB`, ... powers of 1000.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let invalid = || anyhow!("invalid size `{}`, expected eg: 20GiB, 500M or 10KB", s);
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        _ => return Err(invalid()),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+impl fmt::Display for TrashQuota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrashQuota::Bytes(bytes) => write!(f, "{} bytes", bytes),
+            TrashQuota::Percent(percent) => write!(f, "{}% of its filesystem", percent),
+        }
+    }
 }
 
 pub struct ConfigFile;
@@ -35,10 +127,13 @@ impl ConfigFile {
         if !&config_dir.exists() {
             create_dir_all(&config_dir)?;
         }
+        // written next to it and renamed, so a concurrent run never reads a half written file
+        let tmp_file = config_dir.join(format!("config.toml.{}", std::process::id()));
         fs::write(
-            config_dir.join("config.toml"),
+            &tmp_file,
             "[settings]\ncheck_sha256 = false\nnew_check_sha256 = false",
         )?;
+        fs::rename(tmp_file, config_dir.join("config.toml"))?;
         Ok(())
     }
 }
@@ -56,4 +151,17 @@ mod tests {
             .new_check_sha256;
         assert!(!conf);
     }
+
+    #[test]
+    fn parse_quota() {
+        let quota = |s: &str| s.parse::<TrashQuota>().unwrap();
+        assert_eq!(quota("512"), TrashQuota::Bytes(512));
+        assert_eq!(quota("20GiB"), TrashQuota::Bytes(20 << 30));
+        assert_eq!(quota("1.5 M"), TrashQuota::Bytes(3 << 19));
+        assert_eq!(quota("2gb"), TrashQuota::Bytes(2_000_000_000));
+        assert_eq!(quota("10%"), TrashQuota::Percent(10.0));
+        assert!("10 parsecs".parse::<TrashQuota>().is_err());
+        assert!("150%".parse::<TrashQuota>().is_err());
+        assert!(TrashQuota::Percent(1.0).limit_for(Path::new("/")).unwrap() > 0);
+    }
 }
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::fs::{self, Metadata};
+use std::io;
+use std::os::unix::fs::{lchown, MetadataExt, PermissionsExt};
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset};
+use filetime::FileTime;
+use roxide::{check_root, decode_hex, show_error, DirMeta, ItemMeta};
+
+use super::conflict::{RestoreAction, RestoreReport};
+
+/// Attributes to reapply to a restored path.
+struct Attrs<'a> {
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    mtime: &'a DateTime<FixedOffset>,
+    atime: Option<&'a DateTime<FixedOffset>>,
+    xattrs: &'a BTreeMap<String, String>,
+}
+
+impl<'a> From<&'a ItemMeta> for Attrs<'a> {
+    fn from(item: &'a ItemMeta) -> Self {
+        Self {
+            mode: item.mode,
+            uid: item.uid,
+            gid: item.gid,
+            mtime: &item.mtime,
+            atime: item.atime.as_ref(),
+            xattrs: &item.xattrs,
+        }
+    }
+}
+
+impl<'a> From<&'a DirMeta> for Attrs<'a> {
+    fn from(dir: &'a DirMeta) -> Self {
+        Self {
+            mode: dir.mode,
+            uid: dir.uid,
+            gid: dir.gid,
+            mtime: &dir.mtime,
+            atime: Some(&dir.atime),
+            xattrs: &dir.xattrs,
+        }
+    }
+}
+
+/// Gives the restored item of `report` and the parent directories recreated for it
+/// the attributes they had when the item was trashed, as recorded in `item`.
+///
+/// Mode, times and xattrs are reapplied, ownership only when running as root.
+/// Attributes which can't be set are reported, they don't fail the restore.
+pub fn reapply_attrs(report: &RestoreReport, item: &ItemMeta) {
+    match report.action {
+        RestoreAction::Restored | RestoreAction::Renamed | RestoreAction::Overwritten => {
+            apply(&report.path, &Attrs::from(item))
+        }
+        RestoreAction::Merged | RestoreAction::Skipped | RestoreAction::Missing => {}
+    }
+    // innermost first, restoring the times of a directory changes nothing in its parent
+    for parent in report.created_parents.iter().rev() {
+        if let Some(dir) = item.parents.iter().find(|dir| dir.path == *parent) {
+            apply(parent, &Attrs::from(dir));
+        }
+    }
+}
+
+fn apply(path: &Path, attrs: &Attrs<'_>) {
+    let meta = match path.symlink_metadata() {
+        Ok(meta) => meta,
+        Err(err) => {
+            show_error!("can't restore attributes of `{}`: {}", path.display(), err);
+            return;
+        }
+    };
+    let warn = |what: &str, err: io::Error| {
+        show_error!("can't restore {} of `{}`: {}", what, path.display(), err)
+    };
+    for (name, value) in attrs.xattrs {
+        let Some(value) = decode_hex(value) else {
+            continue;
+        };
+        if xattr::get(path, name).ok().flatten().as_ref() != Some(&value) {
+            if let Err(err) = xattr::set(path, name, &value) {
+                warn(&format!("xattr {}", name), err);
+            }
+        }
+    }
+    // before the mode, changing the owner clears the setuid and setgid bits
+    if check_root() && (meta.uid() != attrs.uid || meta.gid() != attrs.gid) {
+        if let Err(err) = lchown(path, Some(attrs.uid), Some(attrs.gid)) {
+            warn("ownership", err);
+        }
+    }
+    if !meta.is_symlink() && meta.mode() & 0o7777 != attrs.mode {
+        if let Err(err) = fs::set_permissions(path, fs::Permissions::from_mode(attrs.mode)) {
+            warn("mode", err);
+        }
+    }
+    if let Err(err) = set_times(path, &meta, attrs) {
+        warn("times", err);
+    }
+}
+
+fn set_times(path: &Path, meta: &Metadata, attrs: &Attrs<'_>) -> io::Result<()> {
+    let file_time = |time: &DateTime<FixedOffset>| {
+        FileTime::from_unix_time(time.timestamp(), time.timestamp_subsec_nanos())
+    };
+    let mtime = file_time(attrs.mtime);
+    let atime = attrs
+        .atime
+        .map(file_time)
+        .unwrap_or_else(|| FileTime::from_last_access_time(meta));
+    if FileTime::from_last_modification_time(meta) == mtime
+        && FileTime::from_last_access_time(meta) == atime
+    {
+        return Ok(());
+    }
+    if meta.is_symlink() {
+        filetime::set_symlink_file_times(path, atime, mtime)
+    } else {
+        filetime::set_file_times(path, atime, mtime)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use filetime::FileTime;
+    use roxide::{ConflictPolicy, ItemMeta};
+
+    use super::super::conflict::restore_item;
+    use super::reapply_attrs;
+
+    #[test]
+    fn restore_attributes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let original = tmp.path().join("a/b/file.txt");
+        fs::create_dir_all(original.parent().unwrap()).unwrap();
+        fs::write(&original, "contents").unwrap();
+        fs::set_permissions(&original, fs::Permissions::from_mode(0o640)).unwrap();
+        fs::set_permissions(tmp.path().join("a"), fs::Permissions::from_mode(0o750)).unwrap();
+        let old = FileTime::from_unix_time(1_000_000_000, 0);
+        filetime::set_file_times(&original, old, old).unwrap();
+        filetime::set_file_times(tmp.path().join("a/b"), old, old).unwrap();
+        let item = ItemMeta::capture(&original).unwrap();
+
+        // trashed by a copy, which lost the attributes, then its directories were removed
+        let trashed = tmp.path().join("trashed.txt");
+        fs::write(&trashed, "contents").unwrap();
+        fs::remove_dir_all(tmp.path().join("a")).unwrap();
+
+        let report = restore_item(&trashed, &original, ConflictPolicy::Rename).unwrap();
+        assert_eq!(report.created_parents.len(), 2);
+        reapply_attrs(&report, &item);
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+        let mtime =
+            |path: &Path| FileTime::from_last_modification_time(&fs::metadata(path).unwrap());
+        assert_eq!(mode(&original), 0o640);
+        assert_eq!(mtime(&original), old);
+        assert_eq!(mode(&tmp.path().join("a")), 0o750);
+        assert_eq!(mtime(&tmp.path().join("a/b")), old);
+    }
+}
@@ -0,0 +1,326 @@
+use std::fmt;
+use std::fs::{self, create_dir_all};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use roxide::{show_error, ConflictPolicy};
+
+use super::copy::{move_item, remove_all};
+
+/// What happened to a single item on restore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreAction {
+    /// Moved back to its original path
+    Restored,
+    /// The original path was taken, restored next to it under another name
+    Renamed,
+    /// The item at the original path was replaced
+    Overwritten,
+    /// The trashed directory was merged into the directory at the original path
+    Merged,
+    /// The original path was taken, the item was left in the trash
+    Skipped,
+    /// The item is no longer in the trash dir
+    Missing,
+}
+
+/// Report of a single restored item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreReport {
+    pub action: RestoreAction,
+    /// Where the item is now, the trash path if it was skipped.
+    pub path: PathBuf,
+    /// Parent directories which had to be recreated, outermost first.
+    pub created_parents: Vec<PathBuf>,
+}
+
+impl RestoreReport {
+    /// Whether the item left the trash, so its history record is done.
+    pub fn is_done(&self) -> bool {
+        self.action != RestoreAction::Skipped
+    }
+}
+
+impl fmt::Display for RestoreReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = self.path.display();
+        match self.action {
+            RestoreAction::Restored => write!(f, "restored {}", path),
+            RestoreAction::Renamed => write!(f, "restored as {} (original path exists)", path),
+            RestoreAction::Overwritten => {
+                write!(f, "restored {}, overwriting the existing one", path)
+            }
+            RestoreAction::Merged => write!(f, "merged into {}", path),
+            RestoreAction::Skipped => write!(f, "skipped {}, original path exists", path),
+            RestoreAction::Missing => write!(f, "{} doesn't exist in trash dir", path),
+        }?;
+        for parent in &self.created_parents {
+            write!(f, "\n  recreated directory {}", parent.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves the trashed item `from` back to `to`.
+///
+/// Missing parent directories of `to` are recreated. If something already exists at `to`,
+/// `policy` decides what happens.
+pub fn restore_item(from: &Path, to: &Path, policy: ConflictPolicy) -> io::Result<RestoreReport> {
+    let report = |action, path: &Path, created_parents| RestoreReport {
+        action,
+        path: path.to_path_buf(),
+        created_parents,
+    };
+    if from.symlink_metadata().is_err() {
+        return Ok(report(RestoreAction::Missing, from, Vec::new()));
+    }
+    let created_parents = create_parents(to)?;
+    if to.symlink_metadata().is_err() {
+        move_item(from, to)?;
+        return Ok(report(RestoreAction::Restored, to, created_parents));
+    }
+
+    let policy = match policy {
+        ConflictPolicy::Ask => ask(to)?,
+        policy => policy,
+    };
+    match policy {
+        ConflictPolicy::Skip | ConflictPolicy::Ask => {
+            Ok(report(RestoreAction::Skipped, from, created_parents))
+        }
+        ConflictPolicy::Overwrite => {
+            overwrite(from, to)?;
+            Ok(report(RestoreAction::Overwritten, to, created_parents))
+        }
+        ConflictPolicy::Merge if from.is_dir() && to.is_dir() && !to.is_symlink() => {
+            merge_dir(from, to)?;
+            Ok(report(RestoreAction::Merged, to, created_parents))
+        }
+        ConflictPolicy::Rename | ConflictPolicy::Merge => {
+            let free = free_path(to);
+            move_item(from, &free)?;
+            Ok(report(RestoreAction::Renamed, &free, created_parents))
+        }
+    }
+}
+
+/// Replaces the existing `to` with the trashed item `from`.
+///
+/// `from` is moved next to `to` first, which may be a copy from another device, then the two
+/// are swapped by renames in the same directory and only then the old `to` is removed.
+/// If anything fails before that, `to` is left as it was and `from` goes back to the trash.
+fn overwrite(from: &Path, to: &Path) -> io::Result<()> {
+    let restored = free_path(to);
+    move_item(from, &restored)?;
+    let replaced = free_path(to);
+    let swapped = fs::rename(to, &replaced).and_then(|()| {
+        fs::rename(&restored, to).inspect_err(|_| {
+            let _ = fs::rename(&replaced, to);
+        })
+    });
+    if let Err(err) = swapped {
+        if let Err(err) = move_item(&restored, from) {
+            show_error!(
+                "can't move `{}` back to the trash: {}",
+                restored.display(),
+                err
+            );
+        }
+        return Err(err);
+    }
+    if let Err(err) = remove_all(&replaced) {
+        show_error!(
+            "restored `{}`, but can't remove the item it replaced, it is at {}: {}",
+            to.display(),
+            replaced.display(),
+            err
+        );
+    }
+    Ok(())
+}
+
+/// Creates the missing parent directories of `path`, returns the created ones.
+fn create_parents(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut missing: Vec<PathBuf> = path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| !dir.as_os_str().is_empty() && dir.symlink_metadata().is_err())
+        .map(Path::to_path_buf)
+        .collect();
+    missing.reverse();
+    if let Some(parent) = missing.last() {
+        create_dir_all(parent)?;
+    }
+    Ok(missing)
+}
+
+/// Moves the contents of the directory `from` into the directory `to`.
+///
+/// Sub directories existing in both are merged too. Other conflicting entries are restored
+/// under a free name next to the existing one.
+fn merge_dir(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        match target.symlink_metadata() {
+            Err(_) => move_item(&source, &target)?,
+            Ok(meta) if meta.is_dir() && entry.file_type()?.is_dir() => {
+                merge_dir(&source, &target)?
+            }
+            Ok(_) => move_item(&source, &free_path(&target))?,
+        }
+    }
+    fs::remove_dir(from)
+}
+
+/// Returns a path next to `path` which doesn't exist yet.
+///
+/// `notes.txt` becomes `notes.restored.txt`, then `notes.restored-2.txt` and so on.
+pub fn free_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| match n {
+            1 => path.with_file_name(format!("{}.restored{}", stem, ext)),
+            n => path.with_file_name(format!("{}.restored-{}{}", stem, n, ext)),
+        })
+        .find(|candidate| candidate.symlink_metadata().is_err())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Asks what to do about the existing `path`. Returns `Ask` if the user gave up.
+fn ask(path: &Path) -> io::Result<ConflictPolicy> {
+    loop {
+        print!(
+            "roxide: `{}` already exists. [r]ename, [o]verwrite, [s]kip or [m]erge? ",
+            path.display()
+        );
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(ConflictPolicy::Ask);
+        }
+        match input.trim().to_lowercase().as_str() {
+            "r" | "rename" => return Ok(ConflictPolicy::Rename),
+            "o" | "overwrite" => return Ok(ConflictPolicy::Overwrite),
+            "s" | "skip" => return Ok(ConflictPolicy::Skip),
+            "m" | "merge" => return Ok(ConflictPolicy::Merge),
+            other => show_error!("invalid choice: `{}`", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use roxide::{check_root, ConflictPolicy};
+
+    use super::{free_path, restore_item, RestoreAction};
+
+    fn trashed(dir: &Path, name: &str, contents: &str) -> std::path::PathBuf {
+        let path = dir.join("trash").join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn restore_recreates_parents() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = trashed(tmp.path(), "a.txt", "trashed");
+        let to = tmp.path().join("gone/deeper/a.txt");
+
+        let report = restore_item(&from, &to, ConflictPolicy::Rename).unwrap();
+        assert_eq!(report.action, RestoreAction::Restored);
+        assert_eq!(
+            report.created_parents,
+            vec![tmp.path().join("gone"), tmp.path().join("gone/deeper")]
+        );
+        assert_eq!(fs::read_to_string(&to).unwrap(), "trashed");
+    }
+
+    #[test]
+    fn restore_conflicting_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let to = tmp.path().join("a.txt");
+        fs::write(&to, "recreated").unwrap();
+
+        let from = trashed(tmp.path(), "a.txt", "trashed");
+        let report = restore_item(&from, &to, ConflictPolicy::Skip).unwrap();
+        assert_eq!(report.action, RestoreAction::Skipped);
+        assert!(from.exists());
+
+        let report = restore_item(&from, &to, ConflictPolicy::Rename).unwrap();
+        assert_eq!(report.action, RestoreAction::Renamed);
+        assert_eq!(report.path, tmp.path().join("a.restored.txt"));
+        assert_eq!(fs::read_to_string(&to).unwrap(), "recreated");
+        assert_eq!(free_path(&to), tmp.path().join("a.restored-2.txt"));
+
+        let from = trashed(tmp.path(), "a.txt", "trashed again");
+        let report = restore_item(&from, &to, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(report.action, RestoreAction::Overwritten);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "trashed again");
+        // nothing is left of the swap
+        assert!(!tmp.path().join("a.restored-2.txt").exists());
+    }
+
+    #[test]
+    fn overwrite_keeps_existing_item_on_failure() {
+        let tmp = tempfile::tempdir().unwrap();
+        let to = tmp.path().join("parent/a");
+        fs::create_dir_all(to.join("sub")).unwrap();
+        let from = trashed(tmp.path(), "a", "trashed");
+
+        let report = restore_item(&from, &to, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(report.action, RestoreAction::Overwritten);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "trashed");
+
+        // root can write to read-only directories
+        if check_root() {
+            return;
+        }
+        let from = trashed(tmp.path(), "a", "trashed again");
+        let parent = tmp.path().join("parent");
+        fs::set_permissions(&parent, fs::Permissions::from_mode(0o555)).unwrap();
+        assert!(restore_item(&from, &to, ConflictPolicy::Overwrite).is_err());
+        fs::set_permissions(&parent, fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(fs::read_to_string(&to).unwrap(), "trashed");
+        assert_eq!(fs::read_to_string(&from).unwrap(), "trashed again");
+    }
+
+    #[test]
+    fn restore_merges_directories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let to = tmp.path().join("dir");
+        fs::create_dir_all(to.join("sub")).unwrap();
+        fs::write(to.join("same.txt"), "recreated").unwrap();
+
+        let from = tmp.path().join("trash/dir");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("same.txt"), "trashed").unwrap();
+        fs::write(from.join("sub/only_trashed.txt"), "trashed").unwrap();
+
+        let report = restore_item(&from, &to, ConflictPolicy::Merge).unwrap();
+        assert_eq!(report.action, RestoreAction::Merged);
+        assert!(!from.exists());
+        assert_eq!(
+            fs::read_to_string(to.join("same.txt")).unwrap(),
+            "recreated"
+        );
+        assert_eq!(
+            fs::read_to_string(to.join("same.restored.txt")).unwrap(),
+            "trashed"
+        );
+        assert!(to.join("sub/only_trashed.txt").exists());
+    }
+}
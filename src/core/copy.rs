@@ -0,0 +1,244 @@
+use std::fs::{self, symlink_metadata};
+use std::io;
+use std::os::unix::fs::{symlink, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::Path;
+
+use filetime::FileTime;
+use log::{debug, warn};
+use roxide::hash_file;
+use rustix::fs::{mknodat, FileType, Mode, CWD};
+use walkdir::WalkDir;
+
+/// Recursively copies `from` to `to`.
+///
+/// Symlinks are copied as symlinks, FIFOs and device files are recreated (devices only as root).
+/// Permissions, access and modification times and extended attributes are preserved.
+/// Directory times are set after their contents are copied, since copying into a directory
+/// updates its mtime. Sockets can't be copied, see [`check_copyable`].
+pub fn copy_all(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = symlink_metadata(from)?;
+    let file_type = meta.file_type();
+    if file_type.is_symlink() {
+        symlink(fs::read_link(from)?, to)?;
+        let times = (
+            FileTime::from_last_access_time(&meta),
+            FileTime::from_last_modification_time(&meta),
+        );
+        filetime::set_symlink_file_times(to, times.0, times.1)?;
+        return Ok(());
+    }
+    if file_type.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else if file_type.is_fifo() || file_type.is_block_device() || file_type.is_char_device() {
+        // opening a FIFO to copy it would block until something writes to it
+        mknodat(
+            CWD,
+            to,
+            FileType::from_raw_mode(meta.mode()),
+            Mode::from_raw_mode(meta.mode()),
+            meta.rdev(),
+        )?;
+    } else if file_type.is_socket() {
+        return Err(not_copyable(from));
+    } else {
+        // fs::copy also copies the permission bits
+        fs::copy(from, to)?;
+    }
+    copy_xattrs(from, to);
+    fs::set_permissions(to, fs::Permissions::from_mode(meta.permissions().mode()))?;
+    // set_file_times opens the file, which blocks on a FIFO, `to` isn't a symlink anyway
+    filetime::set_symlink_file_times(
+        to,
+        FileTime::from_last_access_time(&meta),
+        FileTime::from_last_modification_time(&meta),
+    )?;
+    Ok(())
+}
+
+fn not_copyable(path: &Path) -> io::Error {
+    io::Error::other(format!(
+        "`{}` is a socket, it can't be copied to another device",
+        path.display()
+    ))
+}
+
+/// Checks that [`copy_all`] can copy everything in `path`, before anything is copied.
+pub fn check_copyable(path: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(path) {
+        let entry = entry?;
+        if entry.file_type().is_socket() {
+            return Err(not_copyable(entry.path()));
+        }
+    }
+    Ok(())
+}
+
+fn copy_xattrs(from: &Path, to: &Path) {
+    let names = match xattr::list(from) {
+        Ok(names) => names,
+        Err(err) => {
+            debug!("can't list xattrs of {}: {}", from.display(), err);
+            return;
+        }
+    };
+    for name in names {
+        match xattr::get(from, &name) {
+            Ok(Some(value)) => {
+                if let Err(err) = xattr::set(to, &name, &value) {
+                    warn!(
+                        "can't copy xattr {:?} of {}: {}",
+                        name,
+                        from.display(),
+                        err
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(err) => debug!("can't read xattr {:?} of {}: {}", name, from.display(), err),
+        }
+    }
+}
+
+/// Checks that `copy` is an exact copy of `original`.
+///
+/// Both trees must have the same entries of the same types, symlinks must point to the same
+/// targets and every regular file must have the same sha256 hash.
+pub fn verify_copy(original: &Path, copy: &Path) -> io::Result<bool> {
+    let mut originals = WalkDir::new(original).sort_by_file_name().into_iter();
+    let mut copies = WalkDir::new(copy).sort_by_file_name().into_iter();
+    loop {
+        let (a, b) = match (originals.next(), copies.next()) {
+            (None, None) => return Ok(true),
+            (Some(a), Some(b)) => (a?, b?),
+            _ => return Ok(false),
+        };
+        let relative = (
+            a.path().strip_prefix(original).ok(),
+            b.path().strip_prefix(copy).ok(),
+        );
+        if relative.0 != relative.1 || a.file_type() != b.file_type() {
+            debug!("{} differs from {}", a.path().display(), b.path().display());
+            return Ok(false);
+        }
+        let same = if a.file_type().is_symlink() {
+            fs::read_link(a.path())? == fs::read_link(b.path())?
+        } else if a.file_type().is_file() {
+            hash_file(a.path())? == hash_file(b.path())?
+        } else if a.file_type().is_block_device() || a.file_type().is_char_device() {
+            a.metadata()?.rdev() == b.metadata()?.rdev()
+        } else {
+            true
+        };
+        if !same {
+            debug!("{} differs from {}", a.path().display(), b.path().display());
+            return Ok(false);
+        }
+    }
+}
+
+/// Moves `from` to `to`, falling back to copy, verify and remove
+/// if they are on different devices.
+pub fn move_item(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            debug!("{}, copying {} instead", err, from.display());
+            check_copyable(from)?;
+            if let Err(err) = copy_all(from, to) {
+                let _ = remove_all(to);
+                return Err(err);
+            }
+            if !verify_copy(from, to)? {
+                let _ = remove_all(to);
+                return Err(io::Error::other(format!(
+                    "copy of `{}` doesn't match the original",
+                    from.display()
+                )));
+            }
+            remove_all(from)
+        }
+        result => result,
+    }
+}
+
+/// Removes `path`, whatever it is. Symlinks are removed, not followed.
+pub fn remove_all(path: &Path) -> io::Result<()> {
+    if symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::{symlink, FileTypeExt, PermissionsExt};
+    use std::os::unix::net::UnixListener;
+    use std::path::Path;
+
+    use filetime::FileTime;
+
+    use super::{check_copyable, copy_all, verify_copy};
+
+    #[test]
+    fn copy_preserves_tree() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("from");
+        fs::create_dir_all(from.join("sub")).unwrap();
+        fs::write(from.join("sub/file.txt"), "some contents").unwrap();
+        fs::set_permissions(from.join("sub/file.txt"), fs::Permissions::from_mode(0o640))
+            .unwrap();
+        symlink("sub/file.txt", from.join("link")).unwrap();
+        let mtime = FileTime::from_unix_time(1_000_000_000, 0);
+        filetime::set_file_mtime(from.join("sub"), mtime).unwrap();
+
+        let to = tmp.path().join("to");
+        copy_all(&from, &to).unwrap();
+
+        assert!(verify_copy(&from, &to).unwrap());
+        assert_eq!(
+            fs::read_link(to.join("link")).unwrap(),
+            Path::new("sub/file.txt")
+        );
+        let meta = fs::metadata(to.join("sub/file.txt")).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o777, 0o640);
+        let meta = fs::metadata(to.join("sub")).unwrap();
+        assert_eq!(FileTime::from_last_modification_time(&meta), mtime);
+
+        fs::write(to.join("sub/file.txt"), "other contents").unwrap();
+        assert!(!verify_copy(&from, &to).unwrap());
+    }
+
+    #[test]
+    fn copy_special_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("from");
+        fs::create_dir(&from).unwrap();
+        rustix::fs::mknodat(
+            rustix::fs::CWD,
+            from.join("fifo"),
+            rustix::fs::FileType::Fifo,
+            rustix::fs::Mode::from_raw_mode(0o600),
+            0,
+        )
+        .unwrap();
+
+        // would block forever if the FIFO was opened
+        let to = tmp.path().join("to");
+        check_copyable(&from).unwrap();
+        copy_all(&from, &to).unwrap();
+        assert!(fs::symlink_metadata(to.join("fifo"))
+            .unwrap()
+            .file_type()
+            .is_fifo());
+        assert!(verify_copy(&from, &to).unwrap());
+
+        let _socket = UnixListener::bind(from.join("socket")).unwrap();
+        let err = check_copyable(&from).unwrap_err();
+        assert!(err.to_string().contains("is a socket"), "{}", err);
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use roxide::{hash_file, to_hex};
+use similar::TextDiff;
+
+use super::entries::human_size;
+use super::peek::{as_text, pick, TrashedFile};
+
+/// How much of a file is read to tell whether it is text.
+const SNIFF_LEN: u64 = 8 * 1024;
+
+/// One side of a diff, a trashed version of a file or the file at its original path.
+struct Side {
+    path: PathBuf,
+    /// Shown in the header, with the deletion time for trashed versions
+    label: String,
+    /// Hex encoded sha256 recorded when the file was trashed
+    sha256: Option<String>,
+}
+
+impl Side {
+    fn trashed(file: &TrashedFile) -> Self {
+        Self {
+            path: file.path.clone(),
+            label: format!(
+                "{} (trashed {})",
+                file.original.display(),
+                file.entry.time.format("%Y-%m-%d %H:%M:%S")
+            ),
+            // only recorded for the trashed item itself, not for files in a trashed dir
+            sha256: match file.path == file.entry.trash_path {
+                true => file
+                    .entry
+                    .log
+                    .as_ref()
+                    .and_then(|log| log.item())
+                    .and_then(|item| item.sha256.clone()),
+                false => None,
+            },
+        }
+    }
+
+    fn size(&self) -> Result<u64> {
+        let meta = self
+            .path
+            .metadata()
+            .map_err(|err| anyhow!("can't read `{}`: {}", self.label, err))?;
+        if meta.is_dir() {
+            return Err(anyhow!("`{}` is a directory, see `roxide ls`", self.label));
+        }
+        Ok(meta.len())
+    }
+
+    fn sha256(&self) -> Result<String> {
+        match &self.sha256 {
+            Some(sha256) => Ok(sha256.clone()),
+            None => Ok(to_hex(&hash_file(&self.path)?)),
+        }
+    }
+}
+
+/// Whether the start of the file at `path` looks like text, see [`as_text`].
+fn starts_as_text(path: &Path) -> Result<bool> {
+    let mut prefix = Vec::new();
+    File::open(path)?.take(SNIFF_LEN).read_to_end(&mut prefix)?;
+    Ok(as_text(&prefix, true).is_some())
+}
+
+/// `roxide diff`, prints a unified diff from the trashed version of `target` picked with `op`,
+/// see [`pick`], to the file at its original path, or to the version trashed by `against`.
+///
+/// Binary files are compared by size and sha256 without reading them into memory,
+/// nothing is printed if the files are the same.
+pub fn init_diff(
+    target: &str,
+    op: Option<&str>,
+    against: Option<&str>,
+    context: usize,
+) -> Result<()> {
+    let trashed = pick(target, op)?;
+    let old = Side::trashed(&trashed);
+    let new = match against {
+        Some(against) => Side::trashed(&pick(target, Some(against))?),
+        None => Side {
+            path: trashed.original.clone(),
+            label: trashed.original.display().to_string(),
+            sha256: None,
+        },
+    };
+    let old_size = old.size()?;
+    let new_size = new.size()?;
+    if starts_as_text(&old.path)? && starts_as_text(&new.path)? {
+        let old_bytes = fs::read(&old.path)?;
+        let new_bytes = fs::read(&new.path)?;
+        if let (Some(old_text), Some(new_text)) =
+            (as_text(&old_bytes, false), as_text(&new_bytes, false))
+        {
+            if old_text != new_text {
+                print!(
+                    "{}",
+                    TextDiff::from_lines(old_text, new_text)
+                        .unified_diff()
+                        .context_radius(context)
+                        .header(&old.label, &new.label)
+                );
+            }
+            return Ok(());
+        }
+    }
+    let old_sha256 = old.sha256()?;
+    let new_sha256 = new.sha256()?;
+    if old_size == new_size && old_sha256 == new_sha256 {
+        return Ok(());
+    }
+    println!("binary files differ");
+    for (side, size, sha256) in [(&old, old_size, old_sha256), (&new, new_size, new_sha256)] {
+        println!("  {}: {}, sha256 {}", side.label, human_size(size), sha256);
+    }
+    Ok(())
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local};
+use roxide::{DuArgs, FileKind, OutputFormat};
+use serde::Serialize;
+
+use super::entries::{count, human_size, trash_entries, TrashEntry};
+use super::gc::entry_size;
+
+/// Upper bounds in days of the age buckets, and their names.
+const AGE_BUCKETS: [(i64, &str); 6] = [
+    (1, "less than a day"),
+    (7, "1 to 7 days"),
+    (30, "1 to 4 weeks"),
+    (90, "1 to 3 months"),
+    (365, "3 to 12 months"),
+    (i64::MAX, "more than a year"),
+];
+
+/// Items counted by `roxide du` under one name, eg: an extension.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct Usage {
+    name: String,
+    items: usize,
+    bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct LargeItem {
+    original_path: PathBuf,
+    trash_path: PathBuf,
+    deleted: DateTime<FixedOffset>,
+    bytes: u64,
+    op: Option<String>,
+}
+
+/// What `roxide du` prints.
+#[derive(Debug, Serialize)]
+struct DuReport {
+    items: usize,
+    bytes: u64,
+    /// Largest first, as are the extensions and operations
+    by_dir: Vec<Usage>,
+    by_extension: Vec<Usage>,
+    /// Newest first, empty buckets are left out
+    by_age: Vec<Usage>,
+    by_op: Vec<Usage>,
+    largest: Vec<LargeItem>,
+}
+
+/// Adds up items by name, keeping the names in the order they first come.
+#[derive(Default)]
+struct Tally {
+    usage: Vec<Usage>,
+    index: HashMap<String, usize>,
+}
+
+impl Tally {
+    fn add(&mut self, name: String, bytes: u64) {
+        let index = *self.index.entry(name.clone()).or_insert_with(|| {
+            self.usage.push(Usage {
+                name,
+                items: 0,
+                bytes: 0,
+            });
+            self.usage.len() - 1
+        });
+        self.usage[index].items += 1;
+        self.usage[index].bytes += bytes;
+    }
+
+    fn largest_first(mut self) -> Vec<Usage> {
+        self.usage
+            .sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+        self.usage
+    }
+}
+
+/// The directory `original` is counted in: its first `depth` parent directories below `home`,
+/// written with `~`, or below `/`.
+fn top_dir(original: &Path, home: Option<&Path>, depth: usize) -> String {
+    let (base, rest) = match home.and_then(|home| original.strip_prefix(home).ok()) {
+        Some(rest) => ("~", rest),
+        None => ("", original.strip_prefix("/").unwrap_or(original)),
+    };
+    // only the parents, the item itself may not be a directory
+    let dirs: Vec<String> = rest
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .take(depth)
+        .map(|dir| dir.as_os_str().to_string_lossy().to_string())
+        .collect();
+    match (base, dirs.is_empty()) {
+        ("~", true) => "~".to_string(),
+        (base, _) => format!("{}/{}", base, dirs.join("/")),
+    }
+}
+
+/// The lowercase extension of a trashed file, `(dir)` for directories.
+fn extension(entry: &TrashEntry) -> String {
+    let kind = match entry.log.as_ref().and_then(|log| log.item()) {
+        Some(item) => Some(item.file_type),
+        None => entry
+            .trash_path
+            .symlink_metadata()
+            .ok()
+            .map(|meta| FileKind::from(&meta)),
+    };
+    if kind == Some(FileKind::Dir) {
+        return "(dir)".to_string();
+    }
+    entry
+        .original
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| "(none)".to_string())
+}
+
+/// The name of the age bucket of an item trashed `days` ago.
+fn age_bucket(days: i64) -> &'static str {
+    AGE_BUCKETS
+        .iter()
+        .find(|(limit, _)| days < *limit)
+        .map_or(AGE_BUCKETS[AGE_BUCKETS.len() - 1].1, |(_, name)| name)
+}
+
+fn report(entries: Vec<TrashEntry>, args: &DuArgs) -> DuReport {
+    let home = dirs::home_dir();
+    let now = Local::now().fixed_offset();
+    let mut by_dir = Tally::default();
+    let mut by_extension = Tally::default();
+    let mut by_age = Tally::default();
+    let mut by_op = Tally::default();
+    let (mut items, mut bytes) = (0, 0);
+    let mut sized: Vec<(TrashEntry, u64)> = Vec::new();
+    for entry in entries {
+        let size = entry_size(&entry);
+        items += 1;
+        bytes += size;
+        by_dir.add(top_dir(&entry.original, home.as_deref(), args.depth), size);
+        by_extension.add(extension(&entry), size);
+        let days = (now - entry.time).num_days();
+        by_age.add(age_bucket(days).to_string(), size);
+        let op = match &entry.log {
+            Some(log) => log.op().to_string(),
+            None => "(other tools)".to_string(),
+        };
+        by_op.add(op, size);
+        sized.push((entry, size));
+    }
+    // stable, items of the same size stay oldest first
+    sized.sort_by(|(_, a), (_, b)| b.cmp(a));
+    by_age
+        .usage
+        .sort_by_key(|usage| AGE_BUCKETS.iter().position(|(_, name)| *name == usage.name));
+    DuReport {
+        items,
+        bytes,
+        by_dir: by_dir.largest_first(),
+        by_extension: by_extension.largest_first(),
+        by_age: by_age.usage,
+        by_op: by_op.largest_first(),
+        largest: sized
+            .into_iter()
+            .take(args.top)
+            .map(|(entry, bytes)| LargeItem {
+                op: entry.log.map(|log| log.op().to_string()),
+                original_path: entry.original,
+                trash_path: entry.trash_path,
+                deleted: entry.time,
+                bytes,
+            })
+            .collect(),
+    }
+}
+
+/// `roxide du`, breaks down the size of the items in the trash dirs, see [`trash_entries`].
+///
+/// Sizes are the ones recorded in history when the items were trashed if they were,
+/// see [`entry_size`], so the trash is only walked for items trashed by other tools.
+pub fn init_du(args: &DuArgs) -> Result<()> {
+    let report = report(trash_entries()?, args);
+    match args.format {
+        OutputFormat::Table => print_report(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            writer.write_record(["group", "name", "items", "bytes"])?;
+            let groups = [
+                ("dir", &report.by_dir),
+                ("extension", &report.by_extension),
+                ("age", &report.by_age),
+                ("op", &report.by_op),
+            ];
+            for (group, usage) in groups {
+                for usage in usage {
+                    writer.write_record([
+                        group,
+                        &usage.name,
+                        &usage.items.to_string(),
+                        &usage.bytes.to_string(),
+                    ])?;
+                }
+            }
+            for item in &report.largest {
+                writer.write_record([
+                    "largest",
+                    &item.original_path.to_string_lossy(),
+                    "1",
+                    &item.bytes.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn print_report(report: &DuReport) {
+    if report.items == 0 {
+        println!("Trash is empty");
+        return;
+    }
+    println!(
+        "trash: {}, {}",
+        count(report.items),
+        human_size(report.bytes)
+    );
+    let sections = [
+        ("BY DIRECTORY", &report.by_dir),
+        ("BY EXTENSION", &report.by_extension),
+        ("BY AGE", &report.by_age),
+        ("BY OPERATION", &report.by_op),
+    ];
+    for (title, usage) in sections {
+        println!("\n{}", title);
+        for usage in usage {
+            println!(
+                "  {:>10}  {:>11}  {}",
+                human_size(usage.bytes),
+                count(usage.items),
+                usage.name
+            );
+        }
+    }
+    println!("\nLARGEST ITEMS");
+    for item in &report.largest {
+        println!(
+            "  {:>10}  {}  {}",
+            human_size(item.bytes),
+            item.deleted.format("%Y-%m-%d %H:%M:%S"),
+            item.original_path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{age_bucket, top_dir};
+
+    #[test]
+    fn group_by_top_dir() {
+        let home = Some(Path::new("/home/alice"));
+        let top = |path: &str, depth| top_dir(Path::new(path), home, depth);
+        assert_eq!(top("/home/alice/src/roxide/main.rs", 1), "~/src");
+        assert_eq!(top("/home/alice/src/roxide/main.rs", 2), "~/src/roxide");
+        assert_eq!(top("/home/alice/src/roxide/main.rs", 5), "~/src/roxide");
+        assert_eq!(top("/home/alice/notes.txt", 1), "~");
+        assert_eq!(top("/tmp/build/out.o", 1), "/tmp");
+        assert_eq!(top("/out.o", 1), "/");
+        assert_eq!(top("/home/bob/notes.txt", 1), "/home");
+    }
+
+    #[test]
+    fn age_buckets() {
+        assert_eq!(age_bucket(0), "less than a day");
+        assert_eq!(age_bucket(7), "1 to 4 weeks");
+        assert_eq!(age_bucket(4000), "more than a year");
+    }
+}
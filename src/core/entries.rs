@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset};
+use roxide::{LocalLogFile, TrashDir, TrashLog};
+
+/// An item in a trash dir, either recorded in history by roxide
+/// or described by a `.trashinfo` file of another trash implementation.
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    /// Where the item was trashed from.
+    pub original: PathBuf,
+    pub trash_path: PathBuf,
+    pub time: DateTime<FixedOffset>,
+    /// The history record, `None` for items trashed by other tools.
+    pub log: Option<TrashLog>,
+}
+
+impl From<TrashLog> for TrashEntry {
+    fn from(log: TrashLog) -> Self {
+        Self {
+            original: log.from().clone(),
+            trash_path: log.to().clone(),
+            time: log.time,
+            log: Some(log),
+        }
+    }
+}
+
+/// Returns every item which is still in a trash dir, oldest first.
+///
+/// These are the history records whose item is still in the trash, followed by the items described by
+/// `.trashinfo` files in the home trash and in every other trash dir known from history.
+pub fn trash_entries() -> Result<Vec<TrashEntry>> {
+    let logs = LocalLogFile::read()?;
+    let mut trash_dirs: Vec<TrashDir> = TrashDir::home().into_iter().collect();
+    for log in logs.iter().filter(|log| log.is_trashed()) {
+        if let Some(dir) = TrashDir::containing(log.to()) {
+            if !trash_dirs.contains(&dir) {
+                trash_dirs.push(dir);
+            }
+        }
+    }
+
+    let mut entries: Vec<TrashEntry> = logs
+        .into_iter()
+        .filter(|log| log.is_trashed() && log.to().symlink_metadata().is_ok())
+        .map(TrashEntry::from)
+        .collect();
+    let known: HashSet<PathBuf> = entries.iter().map(|e| e.trash_path.clone()).collect();
+    for dir in &trash_dirs {
+        for (trash_path, info) in dir.read_infos() {
+            if known.contains(&trash_path) || trash_path.symlink_metadata().is_err() {
+                continue;
+            }
+            if let Some(time) = info.deletion_date_local() {
+                entries.push(TrashEntry {
+                    original: info.path,
+                    trash_path,
+                    time: time.fixed_offset(),
+                    log: None,
+                });
+            }
+        }
+    }
+    entries.sort_by_key(|entry| entry.time);
+    Ok(entries)
+}
+
+/// Returns the items in the trash dirs of `entries` and the home trash which have
+/// neither a history record nor a `.trashinfo` file, sorted by path.
+pub fn orphans(entries: &[TrashEntry]) -> Vec<PathBuf> {
+    let mut trash_dirs: Vec<TrashDir> = TrashDir::home().into_iter().collect();
+    for entry in entries {
+        if let Some(dir) = TrashDir::containing(&entry.trash_path) {
+            if !trash_dirs.contains(&dir) {
+                trash_dirs.push(dir);
+            }
+        }
+    }
+    let known: HashSet<&PathBuf> = entries.iter().map(|entry| &entry.trash_path).collect();
+    let mut orphans: Vec<PathBuf> = trash_dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir.files()).ok())
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| !known.contains(path))
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+/// Formats `bytes` with a binary unit, eg: `1.5 KiB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
+
+/// `items` with the noun, eg: `1 item` or `3 items`.
+pub fn count(items: usize) -> String {
+    match items {
+        1 => "1 item".to_string(),
+        items => format!("{} items", items),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::human_size;
+
+    #[test]
+    fn human_sizes() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
@@ -0,0 +1,207 @@
+use std::fs::{File, Metadata};
+use std::io::{self, BufRead, BufReader};
+use std::path::{absolute, Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local};
+use glob::Pattern;
+use regex::{bytes, Regex};
+use roxide::{parse_size, show_error, time, FileKind, FindArgs, OutputFormat};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use super::entries::{trash_entries, TrashEntry};
+
+/// The filters of `roxide find`, parsed.
+#[derive(Debug, Default)]
+struct Filter {
+    name: Option<Pattern>,
+    regex: Option<Regex>,
+    dir: Option<PathBuf>,
+    since: Option<DateTime<Local>>,
+    until: Option<DateTime<Local>>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    contains: Option<bytes::Regex>,
+}
+
+impl Filter {
+    fn new(args: &FindArgs) -> Result<Self> {
+        Ok(Self {
+            name: args.name.as_deref().map(Pattern::new).transpose()?,
+            regex: args.regex.as_deref().map(Regex::new).transpose()?,
+            dir: args.dir.as_deref().map(absolute).transpose()?,
+            since: args
+                .since
+                .as_deref()
+                .map(|since| time::parse(since, false))
+                .transpose()?,
+            until: args
+                .until
+                .as_deref()
+                .map(|until| time::parse(until, true))
+                .transpose()?,
+            min_size: args.min_size.as_deref().map(parse_size).transpose()?,
+            max_size: args.max_size.as_deref().map(parse_size).transpose()?,
+            contains: args
+                .contains
+                .as_deref()
+                .map(bytes::Regex::new)
+                .transpose()?,
+        })
+    }
+
+    /// Whether files of the trashed item `entry` may match, by its deletion time and original path.
+    fn matches_entry(&self, entry: &TrashEntry) -> bool {
+        self.since.is_none_or(|since| entry.time >= since)
+            && self.until.is_none_or(|until| entry.time <= until)
+            && self.dir.as_ref().is_none_or(|dir| {
+                entry.original.starts_with(dir) || dir.starts_with(&entry.original)
+            })
+    }
+
+    /// Whether only regular files can match, as only they have a size and contents.
+    fn files_only(&self) -> bool {
+        self.min_size.is_some() || self.max_size.is_some() || self.contains.is_some()
+    }
+
+    /// Whether the trashed file at `path`, which was at `original`, matches.
+    ///
+    /// Names are matched against the original name, the name in the trash dir may have been
+    /// changed to make it unique.
+    fn matches(&self, original: &Path, path: &Path, meta: &Metadata) -> io::Result<bool> {
+        let name = original
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let in_dir = self
+            .dir
+            .as_ref()
+            .is_none_or(|dir| original.starts_with(dir));
+        let named = self.name.as_ref().is_none_or(|glob| glob.matches(&name))
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&name));
+        if !in_dir || !named {
+            return Ok(false);
+        }
+        if !self.files_only() {
+            return Ok(true);
+        }
+        if !meta.is_file()
+            || self.min_size.is_some_and(|min| meta.len() < min)
+            || self.max_size.is_some_and(|max| meta.len() > max)
+        {
+            return Ok(false);
+        }
+        match &self.contains {
+            Some(contains) => has_match(contains, path),
+            None => Ok(true),
+        }
+    }
+}
+
+/// Whether a line of the file at `path` matches `regex`, the file is read a line at a time.
+fn has_match(regex: &bytes::Regex, path: &Path) -> io::Result<bool> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        if regex.is_match(&line) {
+            return Ok(true);
+        }
+        line.clear();
+    }
+    Ok(false)
+}
+
+/// A file found by `roxide find`.
+#[derive(Debug, Serialize)]
+struct FindRow {
+    original_path: PathBuf,
+    trash_path: PathBuf,
+    deleted: DateTime<FixedOffset>,
+    file_type: FileKind,
+    /// Size in bytes, of the directory entry itself for directories
+    size: u64,
+    /// The original path of the trashed item the file is in, what `roxide restore` takes
+    trashed_item: PathBuf,
+}
+
+/// Searches the items in the trash dirs, see [`trash_entries`], and everything inside
+/// the trashed directories, for the files matching `filter`.
+fn find(filter: &Filter) -> Result<Vec<FindRow>> {
+    let mut rows = Vec::new();
+    for entry in trash_entries()?
+        .into_iter()
+        .filter(|entry| filter.matches_entry(entry))
+    {
+        let walker = WalkDir::new(&entry.trash_path)
+            .follow_root_links(false)
+            .sort_by_file_name();
+        for file in walker {
+            let file = match file {
+                Ok(file) => file,
+                Err(err) => {
+                    show_error!("can't search `{}`: {}", entry.trash_path.display(), err);
+                    continue;
+                }
+            };
+            let original = match file.path().strip_prefix(&entry.trash_path) {
+                Ok(relative) if relative.as_os_str().is_empty() => entry.original.clone(),
+                Ok(relative) => entry.original.join(relative),
+                Err(_) => continue,
+            };
+            let meta = match file.metadata() {
+                Ok(meta) => meta,
+                Err(err) => {
+                    show_error!("can't search `{}`: {}", file.path().display(), err);
+                    continue;
+                }
+            };
+            match filter.matches(&original, file.path(), &meta) {
+                Ok(true) => rows.push(FindRow {
+                    original_path: original,
+                    trash_path: file.path().to_path_buf(),
+                    deleted: entry.time,
+                    file_type: FileKind::from(&meta),
+                    size: meta.len(),
+                    trashed_item: entry.original.clone(),
+                }),
+                Ok(false) => {}
+                Err(err) => show_error!("can't read `{}`: {}", file.path().display(), err),
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// `roxide find`, prints the trashed files matching `args`, oldest trashed first.
+///
+/// The table is the trashed item each file is in, its original path and its trash path separated
+/// by tabs, without a header, so the items can be piped into `cut -f1` and `roxide restore`
+/// or `roxide purge`. A file inside a trashed directory names the whole directory there.
+pub fn init_find(args: &FindArgs) -> Result<()> {
+    let rows = find(&Filter::new(args)?)?;
+    match args.format {
+        OutputFormat::Table => {
+            for row in &rows {
+                println!(
+                    "{}\t{}\t{}",
+                    row.trashed_item.display(),
+                    row.original_path.display(),
+                    row.trash_path.display()
+                );
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,257 @@
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use log::debug;
+use roxide::{
+    item_size, show_error, time, verbose, Config, LocalDataDir, LocalLogFile, OpId, StateLock,
+    TrashDir, TrashQuota,
+};
+
+use super::copy::remove_all;
+use super::entries::{count, human_size, trash_entries, TrashEntry};
+use super::history::remove_trash_info;
+use super::rm::trashed_count;
+
+/// Name of the file in the data dir whose mtime is the last automatic gc.
+const GC_STAMP: &str = "gc.stamp";
+/// How often the automatic gc runs if `gc_interval` isn't set.
+const DEFAULT_GC_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// What a gc removed, or would remove on a dry run.
+#[derive(Debug, Default)]
+pub struct GcReport {
+    /// The removed items with their size
+    pub items: Vec<(TrashEntry, u64)>,
+    pub bytes: u64,
+    /// Items which weren't evicted although they alone are over the limit, see [`enforce_quota`]
+    pub kept: Vec<(TrashEntry, u64)>,
+}
+
+/// Permanently removes every unpinned item trashed before `cutoff` from the trash dirs, and marks
+/// their history records as purged.
+///
+/// This includes items trashed by other tools, by the deletion date of their `.trashinfo` file.
+/// With `dry_run` nothing is removed, the report tells what would be.
+pub fn purge_older_than(cutoff: DateTime<Local>, dry_run: bool) -> Result<GcReport> {
+    let _lock = StateLock::acquire()?;
+    let old = trash_entries()?
+        .into_iter()
+        .filter(|entry| entry.time < cutoff && !is_pinned(entry))
+        .map(|entry| {
+            let size = entry_size(&entry);
+            (entry, size)
+        });
+    purge(old, dry_run)
+}
+
+/// Removes the oldest unpinned items of every trash dir which is larger than `quota`,
+/// until it fits.
+///
+/// The size of a trash dir is the size of the items recorded in history or described by
+/// a `.trashinfo` file, as recorded when they were trashed if possible.
+/// Items trashed by the operation `keep` are never evicted, they are reported as kept
+/// if they alone are larger than the limit.
+pub fn enforce_quota(quota: TrashQuota, keep: Option<&OpId>, dry_run: bool) -> Result<GcReport> {
+    let _lock = StateLock::acquire()?;
+    let mut by_dir: Vec<(TrashDir, Vec<(TrashEntry, u64)>)> = Vec::new();
+    // oldest first
+    for entry in trash_entries()? {
+        let Some(dir) = TrashDir::containing(&entry.trash_path) else {
+            continue;
+        };
+        let size = entry_size(&entry);
+        match by_dir.iter_mut().find(|(other, _)| *other == dir) {
+            Some((_, entries)) => entries.push((entry, size)),
+            None => by_dir.push((dir, vec![(entry, size)])),
+        }
+    }
+    let mut evicted = Vec::new();
+    let mut kept = Vec::new();
+    for (dir, entries) in by_dir {
+        let limit = quota.limit_for(&dir.files())?;
+        let mut total: u64 = entries.iter().map(|(_, size)| size).sum();
+        let (current, entries): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|(entry, _)| keep.is_some_and(|keep| is_of_op(entry, keep)));
+        for (entry, size) in entries {
+            if total <= limit {
+                break;
+            }
+            if !is_pinned(&entry) {
+                total -= size;
+                evicted.push((entry, size));
+            }
+        }
+        if current.iter().map(|(_, size)| size).sum::<u64>() > limit {
+            kept.extend(current);
+        }
+    }
+    let mut report = purge(evicted.into_iter(), dry_run)?;
+    report.kept = kept;
+    Ok(report)
+}
+
+fn is_of_op(entry: &TrashEntry, op: &OpId) -> bool {
+    entry.log.as_ref().is_some_and(|log| log.op() == op)
+}
+
+fn is_pinned(entry: &TrashEntry) -> bool {
+    entry.log.as_ref().is_some_and(|log| log.pinned)
+}
+
+/// Size of a trashed item, as recorded when it was trashed if it was.
+pub fn entry_size(entry: &TrashEntry) -> u64 {
+    entry
+        .log
+        .as_ref()
+        .and_then(|log| log.item())
+        .map(|item| item.size)
+        .or_else(|| item_size(&entry.trash_path))
+        .unwrap_or(0)
+}
+
+/// Removes `items` from their trash dirs and marks their history records as purged.
+///
+/// The caller holds the [`StateLock`].
+pub fn purge(items: impl Iterator<Item = (TrashEntry, u64)>, dry_run: bool) -> Result<GcReport> {
+    let mut report = GcReport::default();
+    for (entry, size) in items {
+        if !dry_run {
+            if let Err(err) = remove_all(&entry.trash_path) {
+                show_error!("can't purge `{}`: {}", entry.trash_path.display(), err);
+                continue;
+            }
+            remove_trash_info(&entry.trash_path);
+        }
+        report.bytes += size;
+        report.items.push((entry, size));
+    }
+    if !dry_run && !report.items.is_empty() {
+        let purged: Vec<&PathBuf> = report
+            .items
+            .iter()
+            .map(|(entry, _)| &entry.trash_path)
+            .collect();
+        let logs: Vec<_> = LocalLogFile::read()?
+            .into_iter()
+            .map(|log| match log.is_trashed() && purged.contains(&log.to()) {
+                true => log.purged(),
+                false => log,
+            })
+            .collect();
+        LocalLogFile::rewrite(&logs)?;
+    }
+    Ok(report)
+}
+
+/// `roxide gc`, purges the items trashed before `older_than`, see [`time::parse`],
+/// then evicts items from trash dirs over the configured `max_trash_size`.
+pub fn init_gc(
+    older_than: Option<&str>,
+    config: &Config,
+    dry_run: bool,
+    verbose: bool,
+) -> Result<()> {
+    let quota = config.settings.quota()?;
+    if older_than.is_none() && quota.is_none() {
+        return Err(anyhow!(
+            "nothing to do, give an AGE or set `max_trash_size` in the config file"
+        ));
+    }
+    if let Some(older_than) = older_than {
+        let report = purge_older_than(time::parse(older_than, false)?, dry_run)?;
+        print_report(
+            &report,
+            if dry_run { "would purge" } else { "purged" },
+            verbose || dry_run,
+        );
+    }
+    if let Some(quota) = quota {
+        let report = enforce_quota(quota, None, dry_run)?;
+        print_report(
+            &report,
+            if dry_run { "would evict" } else { "evicted" },
+            true,
+        );
+    }
+    Ok(())
+}
+
+fn print_report(report: &GcReport, action: &str, each: bool) {
+    for (entry, size) in &report.items {
+        verbose!(
+            each,
+            "{} {} ({}, trashed {})",
+            action,
+            entry.original.display(),
+            human_size(*size),
+            entry.time.format("%Y-%m-%d %H:%M:%S")
+        );
+    }
+    println!(
+        "{} {}, {}",
+        action,
+        count(report.items.len()),
+        human_size(report.bytes)
+    );
+}
+
+/// Runs after a remove: the gc configured with `gc_after`, at most once per `gc_interval`,
+/// and, if something was trashed, the eviction of items from trash dirs over `max_trash_size`,
+/// which is always reported.
+///
+/// The time of the last gc is the mtime of `gc.stamp` in the data dir,
+/// it is updated before purging so concurrent runs don't all start one.
+pub fn auto_gc(config: &Config, verbose: bool) -> Result<()> {
+    if let Some(gc_after) = &config.settings.gc_after {
+        let interval = match &config.settings.gc_interval {
+            Some(interval) => humantime::parse_duration(interval)?,
+            None => DEFAULT_GC_INTERVAL,
+        };
+        let stamp = LocalDataDir::new()?.join(GC_STAMP);
+        let last_run = fs::metadata(&stamp).and_then(|meta| meta.modified()).ok();
+        if last_run
+            .and_then(|last_run| SystemTime::now().duration_since(last_run).ok())
+            .is_some_and(|since| since < interval)
+        {
+            debug!("skipping gc, it ran at {:?}", last_run);
+        } else {
+            File::create(&stamp)?.set_modified(SystemTime::now())?;
+            let report = purge_older_than(time::parse(gc_after, false)?, false)?;
+            verbose!(
+                verbose && !report.items.is_empty(),
+                "roxide: purged {} trashed more than {} ago, {}",
+                count(report.items.len()),
+                gc_after,
+                human_size(report.bytes)
+            );
+        }
+    }
+    if trashed_count() == 0 {
+        return Ok(());
+    }
+    if let Some(quota) = config.settings.quota()? {
+        // what was just trashed is never evicted right away
+        let report = enforce_quota(quota, Some(OpId::current()), false)?;
+        for (entry, size) in &report.items {
+            println!(
+                "roxide: evicted `{}` ({}) from the trash, it is limited to {}",
+                entry.original.display(),
+                human_size(*size),
+                quota
+            );
+        }
+        for (entry, size) in &report.kept {
+            println!(
+                "roxide: kept `{}` ({}) in the trash, although it alone is over the limit of {}",
+                entry.original.display(),
+                human_size(*size),
+                quota
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,128 +1,381 @@
 use chrono::{DateTime, Local};
-use roxide::{time, trash_dir, verbose, Cli};
-use sha2::{Digest, Sha256};
+use roxide::{
+    hash_file, show_error, time, verbose, Cli, ConflictPolicy, ItemMeta, Journal, JournalEntry,
+    LocalLogFile, LogId, MoveKind, OpId, StateLock, TrashDir, TrashInfo, TrashLog, TrashMeta,
+};
+use anyhow::Error;
 use std::{
-    error::Error,
-    fs::{self, create_dir_all, rename, File, OpenOptions},
-    io::{self, BufRead, BufReader, Write},
-    num::ParseIntError,
-    path::{Path, PathBuf},
-    str::FromStr,
-    vec,
+    error,
+    fs::{self, rename},
+    io,
+    os::unix::ffi::OsStrExt,
+    path::{absolute, Path, PathBuf},
 };
 
-use dirs::data_dir;
 use log::debug;
 
-use super::rm::RoError;
-
-/// # LogId unique id which represents year, month, date, hour, minute and second
-/// in this order itself. ("%Y%m%d%H%M%S")
-#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub struct LogId(u64);
-
-impl From<u64> for LogId {
-    fn from(value: u64) -> Self {
-        LogId(value)
-    }
-}
-
-impl FromStr for LogId {
-    type Err = ParseIntError;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.trim().parse() {
-            Ok(value) => Ok(LogId(value)),
-            Err(e) => Err(e),
-        }
-    }
-}
+use super::attrs::reapply_attrs;
+use super::conflict::{restore_item, RestoreAction, RestoreReport};
+use super::copy::{copy_all, remove_all, verify_copy};
 
 #[derive(Debug, PartialEq, PartialOrd)]
-pub struct TrashMeta {
-    pub file_path: PathBuf,
-    pub trash_path: PathBuf,
+pub struct History {
+    pub log_id: LogId,
+    pub metadata: TrashMeta,
+    /// See [`TrashLog::relative_path`].
+    pub relative_path: Option<PathBuf>,
 }
 
-impl TrashMeta {
-    pub fn new(file_path: PathBuf, trash_path: PathBuf) -> Self {
+impl From<TrashLog> for History {
+    fn from(log: TrashLog) -> Self {
         Self {
-            file_path,
-            trash_path,
+            log_id: log.id,
+            metadata: log.metadata,
+            relative_path: log.relative_path,
         }
     }
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
-pub struct History {
-    pub log_id: LogId,
-    pub metadata: TrashMeta,
-}
-
 impl History {
-    pub fn write<'a>(history: History) -> RoError<'a, ()> {
-        let log_dir = data_dir().unwrap().join("roxide");
-        if !log_dir.exists() {
-            create_dir_all(log_dir).unwrap();
+    /// The history record of this item, trashed now by the current operation.
+    pub fn record(self) -> TrashLog {
+        let mut log = TrashLog {
+            relative_path: self.relative_path,
+            ..TrashLog::new(
+                self.log_id,
+                self.metadata.file_path,
+                self.metadata.trash_path,
+            )
+        };
+        log.metadata.item = self.metadata.item;
+        log
+    }
+
+    /// Restores every item trashed by the operation `op`, or by the last operation if `op` is `None`.
+    ///
+    /// `op` may also be a unique prefix of an operation id.
+    /// With `to`, the items are restored there instead of their original paths, see [`History::destinations`].
+    /// Restored items stay in the history as reverted, so [`History::init_redo`] can trash them again.
+    /// Items which were skipped or failed to restore stay trashed, merged directories are
+    /// recorded as restored and items missing from the trash dir as purged.
+    pub fn init_revert(
+        op: Option<&str>,
+        policy: ConflictPolicy,
+        to: Option<&Path>,
+    ) -> Result<(), Box<dyn error::Error>> {
+        let _lock = StateLock::acquire()?;
+        let (logs, mut kept): (Vec<TrashLog>, Vec<TrashLog>) = LocalLogFile::read()?
+            .into_iter()
+            .partition(TrashLog::is_trashed);
+        let target = match op {
+            Some(op) => Self::find_op(&logs, op)?,
+            None => match logs.last() {
+                Some(last) => last.op().clone(),
+                None => {
+                    println!("Nothing to revert");
+                    return Ok(());
+                }
+            },
+        };
+        debug!("reverting operation: {}", target);
+
+        let (to_revert, others): (Vec<TrashLog>, Vec<TrashLog>) =
+            logs.into_iter().partition(|log| log.op() == &target);
+        kept.extend(others);
+        let targets = match to {
+            Some(to) => Self::destinations(
+                &to_revert
+                    .iter()
+                    .map(|log| (log.from().as_path(), log.relative_path.as_deref()))
+                    .collect::<Vec<_>>(),
+                to,
+            ),
+            None => to_revert.iter().map(|log| log.from().clone()).collect(),
+        };
+        // newest first, so items trashed from inside a trashed directory go back last
+        for (log, target) in to_revert.into_iter().zip(targets).rev() {
+            match Self::revert(log.to(), &target, log.item(), policy) {
+                Ok(report) => {
+                    println!("{}", report);
+                    match report.action {
+                        RestoreAction::Skipped => kept.push(log),
+                        RestoreAction::Restored
+                        | RestoreAction::Renamed
+                        | RestoreAction::Overwritten => kept.push(log.reverted(&report.path)),
+                        // a merged directory can't be split up again by redo
+                        RestoreAction::Merged => kept.push(log.restored(&report.path)),
+                        RestoreAction::Missing => kept.push(log.purged()),
+                    }
+                }
+                Err(err) => {
+                    show_error!("failed to restore `{}`: {}", log.from().display(), err);
+                    kept.push(log);
+                }
+            }
         }
-        let log_file = data_dir().unwrap().join("roxide/history.log");
-        if !log_file.exists() {
-            File::create(&log_file).unwrap();
+        kept.sort_by_key(|log| log.time);
+        LocalLogFile::rewrite(&kept)?;
+        Ok(())
+    }
+
+    /// Where the items with the original paths and [`TrashLog::relative_path`]s of `items`
+    /// go when they are restored to `to`, in the same order.
+    ///
+    /// A single item is restored as `to`, or into it if `to` is a directory or ends with a `/`.
+    /// Several items are restored into the directory `to` keeping their layout: items removed
+    /// by a pattern at their relative path, others relative to the closest directory containing
+    /// all of them.
+    pub fn destinations(items: &[(&Path, Option<&Path>)], to: &Path) -> Vec<PathBuf> {
+        let into_dir = items.len() > 1 || to.is_dir() || to.as_os_str().as_bytes().ends_with(b"/");
+        // so the restored paths recorded in history don't depend on the working directory
+        let to = absolute(to).unwrap_or_else(|_| to.to_path_buf());
+        if !into_dir {
+            return vec![to; items.len()];
         }
-        let mut file = OpenOptions::new()
-            .create(true) // Create the file if it doesn't exist
-            .append(true) // Append to the file if it already exists
-            .open(log_file)?;
+        let common = items
+            .iter()
+            .filter(|(_, relative)| relative.is_none())
+            .filter_map(|(original, _)| original.parent())
+            .reduce(|common, parent| {
+                common
+                    .ancestors()
+                    .find(|ancestor| parent.starts_with(ancestor))
+                    .unwrap_or(common)
+            });
+        items
+            .iter()
+            .map(|(original, relative)| {
+                let relative = relative
+                    .or_else(|| original.strip_prefix(common?).ok())
+                    .or_else(|| original.file_name().map(Path::new))
+                    .unwrap_or(original);
+                to.join(relative)
+            })
+            .collect()
+    }
 
-        writeln!(file, "{}", history.log_id.0)?;
-        writeln!(file, "{}", history.metadata.file_path.to_string_lossy())?;
-        writeln!(file, "{}", history.metadata.trash_path.to_string_lossy())?;
-        writeln!(file, "----------------------------")?;
+    /// Trashes the items of the most recently reverted operation again.
+    ///
+    /// Items keep their history record, so they get the same name in the same trash dir if it is
+    /// still free, and their `.trashinfo` file keeps the original path and deletion date.
+    pub fn init_redo(verbose: bool) -> Result<(), Box<dyn error::Error>> {
+        let _lock = StateLock::acquire()?;
+        let logs = LocalLogFile::read()?;
+        let target = logs
+            .iter()
+            .filter(|log| log.is_reverted())
+            .max_by_key(|log| log.reverted_at)
+            .map(|log| log.op().clone());
+        let Some(target) = target else {
+            println!("Nothing to redo");
+            return Ok(());
+        };
+        debug!("redoing operation: {}", target);
 
+        let mut kept = Vec::with_capacity(logs.len());
+        let mut journal = Vec::new();
+        for log in logs {
+            if !log.is_reverted() || log.op() != &target {
+                kept.push(log);
+                continue;
+            }
+            match Self::retrash(&log) {
+                Ok((retrashed, entry)) => {
+                    verbose!(
+                        verbose,
+                        "Trashed {} to {}",
+                        log.reverted_path().display(),
+                        retrashed.to().display()
+                    );
+                    kept.push(retrashed);
+                    journal.push(entry);
+                }
+                Err(err) => {
+                    show_error!(
+                        "failed to trash `{}` again: {}",
+                        log.reverted_path().display(),
+                        err
+                    );
+                    kept.push(log);
+                }
+            }
+        }
+        LocalLogFile::rewrite(&kept)?;
+        for entry in journal {
+            entry.commit()?;
+        }
         Ok(())
     }
 
-    pub fn init_revert() -> Result<(), Box<dyn Error>> {
-        let log_file = data_dir().unwrap().join("roxide/history.log");
-        let file = fs::File::open(&log_file)?;
-        let reader = BufReader::new(file);
-
-        let mut a_vec: Vec<String> = vec::Vec::new();
-        for line in reader.lines() {
-            let line = line?;
-            a_vec.push(line);
+    /// Moves the reverted item of `log` back into the trash dir it was reverted from.
+    ///
+    /// Returns the updated record, which has to be written to history before the
+    /// journal entry of the move is committed.
+    fn retrash(log: &TrashLog) -> Result<(TrashLog, JournalEntry), Box<dyn error::Error>> {
+        let item_path = log.reverted_path();
+        if item_path.symlink_metadata().is_err() {
+            return Err("it no longer exists".into());
+        }
+        let dir = TrashDir::containing(log.to())
+            .or_else(|| TrashDir::home().ok())
+            .ok_or_else(|| io::Error::other("can't find the trash dir"))?;
+        let trash = Trash {
+            file: item_path,
+            dir,
+        };
+        let trash_name = match log.to().file_name() {
+            Some(name) if trash.dir.is_free(&name.to_string_lossy()) => {
+                name.to_string_lossy().to_string()
+            }
+            _ => trash.trash_name(trash.get_log_id().1),
+        };
+        let info = TrashInfo::new(log.from().clone(), log.time.with_timezone(&Local));
+        let retrashed = log.clone().retrashed(trash.dir.files().join(&trash_name));
+        let entry = Journal::begin(item_path, &retrashed, MoveKind::Rename)?;
+        let (moved, entry) = match trash.move_to_trash_with(item_path, &trash_name, &info) {
+            Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+                entry.abort()?;
+                let entry = Journal::begin(item_path, &retrashed, MoveKind::Copy)?;
+                // redo holds the lock the whole time, this only releases a nested guard
+                let lock = StateLock::acquire()?;
+                let copied = trash
+                    .copy_to_trash_with(item_path, &trash_name, &info, lock, || {
+                        entry.copied().map_err(io::Error::other)
+                    })
+                    .map(|copied| {
+                        if let Err(err) = copied.removed {
+                            show_not_removed(item_path, &err);
+                        }
+                        copied.trash_path
+                    });
+                (copied, entry)
+            }
+            result => (result, entry),
+        };
+        match moved {
+            Ok(_) => Ok((retrashed, entry)),
+            Err(err) => {
+                entry.abort()?;
+                Err(err.into())
+            }
         }
-        Self::revert(
-            a_vec.iter().nth_back(1).unwrap().to_string(),
-            a_vec.iter().nth_back(2).unwrap().to_string(),
-        )?;
+    }
 
-        let vec_lng = a_vec.len();
-        debug!("vec len: {}", &vec_lng);
+    /// Marks the reverted items in history as restored, once something new is trashed
+    /// they can't be redone.
+    pub fn clear_redo() -> Result<(), Error> {
+        let _lock = StateLock::acquire()?;
+        let logs = LocalLogFile::read()?;
+        if !logs.iter().any(TrashLog::is_reverted) {
+            return Ok(());
+        }
+        let logs: Vec<TrashLog> = logs
+            .into_iter()
+            .map(|log| match log.is_reverted() {
+                true => {
+                    let path = log.reverted_path().clone();
+                    log.restored(&path)
+                }
+                false => log,
+            })
+            .collect();
+        LocalLogFile::rewrite(&logs)
+    }
 
-        if vec_lng > 160 {
-            a_vec.truncate(160);
+    /// Finds the operation whose id is `op`, or starts with `op`.
+    pub fn find_op(logs: &[TrashLog], op: &str) -> Result<OpId, Box<dyn error::Error>> {
+        if let Some(log) = logs.iter().find(|log| log.op().as_str() == op) {
+            return Ok(log.op().clone());
         }
-        let mut new_file = fs::File::create(log_file)?;
-        for i in a_vec {
-            writeln!(new_file, "{}", i)?;
+        let mut matches: Vec<&OpId> = logs
+            .iter()
+            .map(TrashLog::op)
+            .filter(|id| id.as_str().starts_with(op))
+            .collect();
+        matches.dedup();
+        match matches.as_slice() {
+            [id] => Ok((*id).clone()),
+            [] => Err(format!("no operation with id `{}` in history", op).into()),
+            _ => Err(format!("operation id `{}` is ambiguous", op).into()),
         }
-
-        Ok(())
     }
-    fn revert(from: String, to: String) -> Result<(), Box<dyn Error>> {
-        if !Path::new(&from).exists() {
-            println!("File Doesn't Exist in Trash dir");
-        } else {
-            rename(from, to)?;
+    /// Moves the trashed item `from` back to `to` and removes its `.trashinfo` file.
+    ///
+    /// See [`restore_item`] for how conflicts and missing parent directories are handled.
+    /// If the attributes of the item were recorded, they are reapplied with [`reapply_attrs`].
+    pub fn revert(
+        from: &Path,
+        to: &Path,
+        item: Option<&ItemMeta>,
+        policy: ConflictPolicy,
+    ) -> Result<RestoreReport, Box<dyn error::Error>> {
+        let report = restore_item(from, to, policy)?;
+        if report.is_done() {
+            remove_trash_info(from);
         }
-        Ok(())
+        if let Some(item) = item {
+            reapply_attrs(&report, item);
+        }
+        Ok(report)
+    }
+
+    /// Marks the records of the items restored from `(trash path, restored path)` as restored.
+    pub fn mark_restored(restored: &[(PathBuf, PathBuf)]) -> Result<(), Error> {
+        if restored.is_empty() {
+            return Ok(());
+        }
+        let _lock = StateLock::acquire()?;
+        let logs = LocalLogFile::read()?
+            .into_iter()
+            .map(|log| {
+                match restored.iter().find(|(trash_path, _)| {
+                    log.is_trashed() && trash_path == log.to()
+                }) {
+                    Some((_, path)) => log.restored(path),
+                    None => log,
+                }
+            })
+            .collect::<Vec<_>>();
+        LocalLogFile::rewrite(&logs)
     }
     pub fn clean() {}
 }
 
+/// Reports an item which was copied into the trash, but whose original couldn't be removed
+/// completely, see [`Copied::removed`].
+pub fn show_not_removed(item_path: &Path, err: &io::Error) {
+    show_error!(
+        "`{}` was copied to the trash, but not all of it could be removed: {}",
+        item_path.display(),
+        err
+    );
+}
+
+/// Removes the `.trashinfo` file of an item which is no longer in the trash.
+pub fn remove_trash_info(trash_path: &Path) {
+    let info_path = TrashDir::containing(trash_path)
+        .zip(trash_path.file_name())
+        .map(|(dir, name)| dir.info_path(&name.to_string_lossy()));
+    if let Some(info_path) = info_path {
+        if let Err(err) = fs::remove_file(&info_path) {
+            debug!("can't remove {}: {}", info_path.display(), err);
+        }
+    }
+}
+
+/// An item copied into the trash dir by [`Trash::copy_to_trash`].
+#[derive(Debug)]
+pub struct Copied {
+    pub trash_path: PathBuf,
+    /// Removing the original once the copy was verified, which may have left part of it behind.
+    /// The item is trashed either way.
+    pub removed: io::Result<()>,
+}
+
 #[derive(Debug)]
 pub struct Trash<'a> {
     pub file: &'a Path,
+    pub dir: TrashDir,
 }
 
 pub trait TrashOps {
@@ -145,8 +398,8 @@ impl TrashOps for Trash<'_> {
     where
         Self: AsRef<std::path::Path>,
     {
-        trash_dir()
-            .unwrap()
+        self.dir
+            .files()
             .join(self.file.file_name().unwrap())
             .exists()
     }
@@ -159,33 +412,38 @@ impl Trash<'_> {
             time::now().format("%Y-%m-%d_%H:%M:%S").to_string(),
         )
     }
+
+    /// Name of the item inside `files` of the trash dir.
+    ///
+    /// The same name is used for its `.trashinfo` file in `info`,
+    /// so a name is only used if it is free in both directories.
+    /// Names are allocated while holding the [`StateLock`], so two processes can't pick the same one.
     pub fn trash_name(&self, log_id: String) -> String {
         let file_stem = self.file.file_stem().unwrap().to_str().unwrap();
         let file_ext = self.file.extension().and_then(|e| e.to_str());
-        let trash_file = trash_dir()
-            .unwrap()
-            .join(self.file.file_name().unwrap())
-            .exists();
-        let trash_file_name = |stem: &str, ext: Option<&str>| -> String {
+        let file_name = self
+            .file
+            .file_name()
+            .map(|t| t.to_string_lossy().to_string())
+            .expect("failed to set trash name");
+        let trash_file_name = |stem: &str, ext: Option<&str>, id: &str| -> String {
             match ext {
-                Some(e) => format!("{}.{}.{}", stem, log_id, e),
-                None => format!("{}.{}", stem, log_id),
+                Some(e) => format!("{}.{}.{}", stem, id, e),
+                None => format!("{}.{}", stem, id),
             }
         };
-        if !trash_file {
-            debug!(
-                "impl Trash struct: {:#?}",
-                self.file
-                    .file_name()
-                    .map(|f| f.to_string_lossy().to_string())
-                    .unwrap()
-            );
-            self.file
-                .file_name()
-                .map(|t| t.to_string_lossy().to_string())
-                .expect("failed to set trash name")
+        if self.dir.is_free(&file_name) {
+            debug!("impl Trash struct: {:#?}", file_name);
+            file_name
         } else {
-            let trash_name = trash_file_name(file_stem, file_ext);
+            // more than one item with this name trashed in the same second
+            let trash_name = (1..)
+                .map(|n| match n {
+                    1 => trash_file_name(file_stem, file_ext, &log_id),
+                    n => trash_file_name(file_stem, file_ext, &format!("{}-{}", log_id, n)),
+                })
+                .find(|name| self.dir.is_free(name))
+                .unwrap();
             debug!(
                 "Trash name from impl: {:#?}",
                 self.file
@@ -198,24 +456,98 @@ impl Trash<'_> {
         }
     }
 
+    /// Moves `item_path` into the trash dir as `trash_name` and writes its `.trashinfo` file.
+    ///
+    /// The info file is written first, as the spec requires. If the move fails it is removed again.
+    pub fn move_to_trash(&self, item_path: &Path, trash_name: &str) -> io::Result<PathBuf> {
+        let info = TrashInfo::new(item_path.to_path_buf(), time::now());
+        self.move_to_trash_with(item_path, trash_name, &info)
+    }
+
+    /// Same as [`Trash::move_to_trash`], with the given `.trashinfo` contents.
+    pub fn move_to_trash_with(
+        &self,
+        item_path: &Path,
+        trash_name: &str,
+        info: &TrashInfo,
+    ) -> io::Result<PathBuf> {
+        self.dir.create()?;
+        let info_path = info.write(&self.dir, trash_name)?;
+        let trash_path = self.dir.files().join(trash_name);
+        if let Err(err) = rename(item_path, &trash_path) {
+            let _ = fs::remove_file(info_path);
+            return Err(err);
+        }
+        Ok(trash_path)
+    }
+
+    /// Copies `item_path` into the trash dir as `trash_name`, for items which can't be renamed
+    /// into it because they are on another device.
+    ///
+    /// The copy is verified with the sha256 hash of every file before `item_path` is removed.
+    /// On any failure until then the copy and its `.trashinfo` file are removed and `item_path`
+    /// is left untouched. `verified` is called once the copy is verified, before `item_path`
+    /// is removed, a failure to remove it is only reported in [`Copied::removed`].
+    ///
+    /// `lock` is released once the `.trashinfo` file reserves `trash_name`, so other processes
+    /// aren't blocked while a large item is copied.
+    pub fn copy_to_trash(
+        &self,
+        item_path: &Path,
+        trash_name: &str,
+        lock: StateLock,
+        verified: impl FnOnce() -> io::Result<()>,
+    ) -> io::Result<Copied> {
+        let info = TrashInfo::new(item_path.to_path_buf(), time::now());
+        self.copy_to_trash_with(item_path, trash_name, &info, lock, verified)
+    }
+
+    /// Same as [`Trash::copy_to_trash`], with the given `.trashinfo` contents.
+    pub fn copy_to_trash_with(
+        &self,
+        item_path: &Path,
+        trash_name: &str,
+        info: &TrashInfo,
+        lock: StateLock,
+        verified: impl FnOnce() -> io::Result<()>,
+    ) -> io::Result<Copied> {
+        self.dir.create()?;
+        let info_path = info.write(&self.dir, trash_name)?;
+        drop(lock);
+        let trash_path = self.dir.files().join(trash_name);
+        let copied = copy_all(item_path, &trash_path).and_then(|()| {
+            if verify_copy(item_path, &trash_path)? {
+                Ok(())
+            } else {
+                Err(io::Error::other(format!(
+                    "copy of `{}` doesn't match the original",
+                    item_path.display()
+                )))
+            }
+        })
+        .and_then(|()| verified());
+        if let Err(err) = copied {
+            if trash_path.symlink_metadata().is_ok() {
+                let _ = remove_all(&trash_path);
+            }
+            let _ = fs::remove_file(info_path);
+            return Err(err);
+        }
+        Ok(Copied {
+            trash_path,
+            removed: remove_all(item_path),
+        })
+    }
+
     /// This function checks the hash of given file and the file in trash directory.
     ///
     /// if hash matches it will return true.
     pub fn compute_sha256(&self, args: &Cli) -> bool {
         if self.file.is_file() {
-            let mut file = File::open(self.file).unwrap();
-            let trash_file = trash_dir().unwrap().join(self.file.file_name().unwrap());
+            let trash_file = self.dir.files().join(self.file.file_name().unwrap());
             if trash_file.exists() {
-                let mut trash_file = File::open(trash_file).unwrap();
-
-                let mut hasher = Sha256::new();
-                let mut hasher2 = Sha256::new();
-
-                io::copy(&mut file, &mut hasher).unwrap();
-                io::copy(&mut trash_file, &mut hasher2).unwrap();
-
-                let hash = hasher.finalize();
-                let hash2 = hasher2.finalize();
+                let hash = hash_file(self.file).unwrap();
+                let hash2 = hash_file(&trash_file).unwrap();
 
                 verbose!(
                     args.verbose,
@@ -232,40 +564,42 @@ impl Trash<'_> {
 
 #[cfg(test)]
 mod test {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Duration;
+
+    use roxide::{check_root, StateLock, TrashDir};
+
+    use super::Trash;
+
     #[test]
-    fn get_last_log() {
-        #[allow(clippy::useless_vec)]
-        let dummy_log = vec![
-            "-------------",
-            "-------------",
-            "-------------",
-            "-------------",
-            "-------------",
-            "20241112214434",
-            "/home/abhi/projects/abhi/github/roxide/file004.org",
-            "/home/abhi/.local/share/Trash/files/file004.2024-11-12_21:44:34.org",
-            "20241112214434",
-            "----------------------------",
-        ];
-        assert_eq!(
-            "20241112214434",
-            &dummy_log.iter().nth_back(4).unwrap().to_string()
-        );
-        assert_eq!(
-            "/home/abhi/projects/abhi/github/roxide/file004.org",
-            &dummy_log.iter().nth_back(3).unwrap().to_string()
-        );
-        assert_eq!(
-            "/home/abhi/.local/share/Trash/files/file004.2024-11-12_21:44:34.org",
-            &dummy_log.iter().nth_back(2).unwrap().to_string()
-        );
-        assert_eq!(
-            "20241112214434",
-            &dummy_log.iter().nth_back(1).unwrap().to_string()
-        );
+    fn copy_keeps_verified_copy() {
+        // root can remove anything
+        if check_root() {
+            return;
+        }
+        let tmp = tempfile::tempdir().unwrap();
+        let item = tmp.path().join("project");
+        fs::create_dir_all(item.join("locked")).unwrap();
+        fs::write(item.join("locked/file.txt"), "contents").unwrap();
+        fs::set_permissions(item.join("locked"), fs::Permissions::from_mode(0o555)).unwrap();
+        let trash = Trash {
+            file: &item,
+            dir: TrashDir {
+                root: tmp.path().join("Trash"),
+            },
+        };
+
+        let lock = StateLock::acquire_at(&tmp.path().join("lock"), Duration::ZERO).unwrap();
+        let copied = trash
+            .copy_to_trash(&item, "project", lock, || Ok(()))
+            .unwrap();
+        assert!(copied.removed.is_err());
         assert_eq!(
-            "----------------------------",
-            &dummy_log.iter().nth_back(0).unwrap().to_string()
+            fs::read_to_string(copied.trash_path.join("locked/file.txt")).unwrap(),
+            "contents"
         );
+        assert!(tmp.path().join("Trash/info/project.trashinfo").exists());
+        fs::set_permissions(item.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
     }
 }
@@ -0,0 +1,153 @@
+use std::io;
+use std::path::{absolute, Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset};
+use roxide::{item_size, ListArgs, OutputFormat, SortKey};
+use serde::Serialize;
+
+use super::entries::{human_size, orphans, trash_entries, TrashEntry};
+use super::restore::Target;
+
+/// An item in the trash as printed by `roxide list`.
+#[derive(Debug, Serialize)]
+struct ListRow {
+    /// Name in the trash dir
+    name: String,
+    /// `None` for orphans
+    original_path: Option<PathBuf>,
+    deleted: Option<DateTime<FixedOffset>>,
+    size: Option<u64>,
+    /// Whether it is in history, so `roxide revert` can bring it back
+    revertible: bool,
+    /// Neither in history nor described by a `.trashinfo` file
+    orphan: bool,
+    /// Never removed by `gc` or to keep the trash under its size limit
+    pinned: bool,
+    op: Option<String>,
+    trash_path: PathBuf,
+}
+
+impl From<TrashEntry> for ListRow {
+    fn from(entry: TrashEntry) -> Self {
+        Self {
+            name: file_name(&entry.trash_path),
+            size: item_size(&entry.trash_path),
+            revertible: entry.log.is_some(),
+            orphan: false,
+            pinned: entry.log.as_ref().is_some_and(|log| log.pinned),
+            op: entry.log.map(|log| log.op().to_string()),
+            original_path: Some(entry.original),
+            deleted: Some(entry.time),
+            trash_path: entry.trash_path,
+        }
+    }
+}
+
+impl ListRow {
+    fn orphan(trash_path: PathBuf) -> Self {
+        Self {
+            name: file_name(&trash_path),
+            original_path: None,
+            deleted: None,
+            size: item_size(&trash_path),
+            revertible: false,
+            orphan: true,
+            pinned: false,
+            op: None,
+            trash_path,
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Lists the items in the trash dirs, see [`trash_entries`] and [`orphans`].
+///
+/// Orphans have no original path, so they are only listed without `--dir` and `--glob`.
+pub fn init_list(args: &ListArgs) -> Result<()> {
+    let dir = args.dir.as_deref().map(absolute).transpose()?;
+    let target = args.glob.as_deref().map(Target::parse).transpose()?;
+    let entries = trash_entries()?;
+    let orphans = match (&dir, &target) {
+        (None, None) => orphans(&entries),
+        _ => Vec::new(),
+    };
+    let mut rows: Vec<ListRow> = entries
+        .into_iter()
+        .filter(|entry| {
+            dir.as_ref()
+                .is_none_or(|dir| entry.original.starts_with(dir))
+                && target
+                    .as_ref()
+                    .is_none_or(|target| target.matches(&entry.original))
+        })
+        .map(ListRow::from)
+        .chain(orphans.into_iter().map(ListRow::orphan))
+        .collect();
+    match args.sort {
+        SortKey::Time => rows.sort_by_key(|row| row.deleted),
+        SortKey::Size => rows.sort_by_key(|row| row.size),
+        SortKey::Name => rows.sort_by(|a, b| a.name.cmp(&b.name)),
+    }
+    if args.reverse {
+        rows.reverse();
+    }
+    match args.format {
+        OutputFormat::Table => print_table(&rows),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn print_table(rows: &[ListRow]) {
+    if rows.is_empty() {
+        println!("Trash is empty");
+        return;
+    }
+    let sizes: Vec<String> = rows
+        .iter()
+        .map(|row| row.size.map(human_size).unwrap_or_else(|| "-".to_string()))
+        .collect();
+    let size_width = sizes.iter().map(String::len).max().unwrap_or(0).max(4);
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:<19}  {:>size_width$}  {:<10}  {:<name_width$}  ORIGINAL PATH",
+        "DELETED", "SIZE", "REVERTIBLE", "NAME"
+    );
+    for (row, size) in rows.iter().zip(&sizes) {
+        let deleted = row
+            .deleted
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let original = match &row.original_path {
+            Some(path) if row.pinned => format!("{} (pinned)", path.display()),
+            Some(path) => path.display().to_string(),
+            None => "orphan, not in history and without .trashinfo".to_string(),
+        };
+        println!(
+            "{:<19}  {:>size_width$}  {:<10}  {:<name_width$}  {}",
+            deleted,
+            size,
+            if row.revertible { "yes" } else { "no" },
+            row.name,
+            original,
+        );
+    }
+}
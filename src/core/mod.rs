@@ -1,2 +1,18 @@
+pub mod attrs;
+pub mod conflict;
+pub mod copy;
+pub mod diff;
+pub mod du;
+pub mod entries;
+pub mod find;
+pub mod gc;
 pub mod history;
+pub mod list;
+pub mod peek;
+pub mod pin;
+pub mod purge;
+pub mod query;
+pub mod recover;
+pub mod restore;
 pub mod rm;
+pub mod tui;
@@ -0,0 +1,233 @@
+use std::fs::{self, File, Metadata};
+use std::io::{self, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{absolute, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use roxide::{show_error, FileKind};
+use walkdir::WalkDir;
+
+use super::entries::{human_size, trash_entries, TrashEntry};
+
+/// A file in the trash, a trashed item or a file inside a trashed directory.
+#[derive(Debug)]
+pub struct TrashedFile {
+    /// The trashed item, which is or contains the file
+    pub entry: TrashEntry,
+    /// Where the file is in the trash dir
+    pub path: PathBuf,
+    /// Where it was before it was trashed
+    pub original: PathBuf,
+}
+
+/// Every trashed version of the file named by `arg`, oldest trashed first.
+///
+/// `arg` is the original path of a trashed item or of a file inside a trashed directory, or the
+/// name of a trashed item in its trash dir, optionally followed by a path inside it.
+/// An item whose original path matches isn't also looked up by its name in the trash dir.
+pub fn resolve(arg: &str) -> Result<Vec<TrashedFile>> {
+    let path = absolute(arg)?;
+    let mut versions = Vec::new();
+    for entry in trash_entries()? {
+        let inside = match path.strip_prefix(&entry.original) {
+            Ok(inside) => inside.to_path_buf(),
+            Err(_) => match entry
+                .trash_path
+                .file_name()
+                .and_then(|name| Path::new(arg).strip_prefix(name).ok())
+            {
+                Some(inside) => inside.to_path_buf(),
+                None => continue,
+            },
+        };
+        let (trashed, original) = match inside.as_os_str().is_empty() {
+            true => (entry.trash_path.clone(), entry.original.clone()),
+            false => (entry.trash_path.join(&inside), entry.original.join(&inside)),
+        };
+        if trashed.symlink_metadata().is_ok() {
+            versions.push(TrashedFile {
+                entry,
+                path: trashed,
+                original,
+            });
+        }
+    }
+    Ok(versions)
+}
+
+/// The version of the file named by `arg` trashed by the operation `op`, or whose id starts
+/// with `op`, or the newest version, see [`resolve`].
+pub fn pick(arg: &str, op: Option<&str>) -> Result<TrashedFile> {
+    let mut versions = resolve(arg)?;
+    if let Some(op) = op {
+        versions.retain(|file| {
+            file.entry
+                .log
+                .as_ref()
+                .is_some_and(|log| log.op().as_str().starts_with(op))
+        });
+    }
+    versions
+        .pop()
+        .ok_or_else(|| anyhow!("nothing in the trash matches `{}`", arg))
+}
+
+/// `bytes` as text, `None` if they have a NUL byte or aren't valid UTF-8.
+///
+/// If `bytes` are only the `prefix` of a file, a character cut at the end is left out.
+pub fn as_text(bytes: &[u8], prefix: bool) -> Option<&str> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text),
+        Err(err) if prefix && err.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..err.valid_up_to()]).ok()
+        }
+        Err(_) => None,
+    }
+}
+
+/// `roxide cat`, writes the trashed files named by `targets` to stdout, see [`pick`].
+pub fn init_cat(targets: &[String], op: Option<&str>) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for arg in targets {
+        let file = match pick(arg, op) {
+            Ok(file) if file.path.is_dir() => {
+                show_error!("`{}` is a directory, see `roxide ls`", arg);
+                continue;
+            }
+            Ok(file) => file,
+            Err(err) => {
+                show_error!("{}", err);
+                continue;
+            }
+        };
+        let copied =
+            File::open(&file.path).and_then(|mut reader| io::copy(&mut reader, &mut stdout));
+        match copied {
+            // eg: piped into `head`
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(err) => show_error!("can't read `{}`: {}", file.path.display(), err),
+            Ok(_) => {}
+        }
+    }
+    match stdout.flush() {
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// `roxide ls`, lists the contents of the trashed directory named by `target`, see [`pick`],
+/// with the paths they had inside it.
+pub fn init_ls(target: &str, op: Option<&str>, recursive: bool) -> Result<()> {
+    let dir = pick(target, op)?;
+    let meta = dir.path.symlink_metadata()?;
+    if !meta.is_dir() {
+        print_file(&dir.path, &meta, &dir.original.display().to_string());
+        return Ok(());
+    }
+    let walker = WalkDir::new(&dir.path)
+        .min_depth(1)
+        .max_depth(if recursive { usize::MAX } else { 1 })
+        .sort_by_file_name();
+    for file in walker {
+        let file = match file {
+            Ok(file) => file,
+            Err(err) => {
+                show_error!("can't list `{}`: {}", dir.path.display(), err);
+                continue;
+            }
+        };
+        let name = file
+            .path()
+            .strip_prefix(&dir.path)
+            .unwrap_or(file.path())
+            .display()
+            .to_string();
+        match file.metadata() {
+            Ok(meta) => print_file(file.path(), &meta, &name),
+            Err(err) => show_error!("can't list `{}`: {}", file.path().display(), err),
+        }
+    }
+    Ok(())
+}
+
+fn print_file(path: &Path, meta: &Metadata, name: &str) {
+    let name = match FileKind::from(meta) {
+        FileKind::Dir => format!("{}/", name),
+        FileKind::Symlink => match fs::read_link(path) {
+            Ok(target) => format!("{} -> {}", name, target.display()),
+            Err(_) => name.to_string(),
+        },
+        FileKind::File | FileKind::Other => name.to_string(),
+    };
+    let mtime = meta
+        .modified()
+        .map(|mtime| {
+            DateTime::<Local>::from(mtime)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| "-".to_string());
+    println!(
+        "{}  {:>10}  {}  {}",
+        mode_string(meta),
+        human_size(meta.len()),
+        mtime,
+        name
+    );
+}
+
+/// The type and permissions of a file as `ls -l` shows them, eg: `drwxr-xr-x`.
+fn mode_string(meta: &Metadata) -> String {
+    let kind = match FileKind::from(meta) {
+        FileKind::File => '-',
+        FileKind::Dir => 'd',
+        FileKind::Symlink => 'l',
+        FileKind::Other => '?',
+    };
+    let permissions =
+        "rwxrwxrwx"
+            .chars()
+            .enumerate()
+            .map(|(bit, c)| match meta.mode() & (0o400 >> bit) {
+                0 => '-',
+                _ => c,
+            });
+    std::iter::once(kind).chain(permissions).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::{as_text, mode_string};
+
+    #[test]
+    fn mode_strings() {
+        let tmp = tempfile::tempdir().unwrap();
+        let file = tmp.path().join("file");
+        fs::write(&file, "contents").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o640)).unwrap();
+        assert_eq!(mode_string(&fs::metadata(&file).unwrap()), "-rw-r-----");
+        fs::set_permissions(tmp.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(
+            mode_string(&fs::metadata(tmp.path()).unwrap()),
+            "drwxr-xr-x"
+        );
+    }
+
+    #[test]
+    fn text_only() {
+        assert_eq!(as_text(b"hello\nworld\n", false), Some("hello\nworld\n"));
+        assert_eq!(as_text(b"\x7fELF\x02\x01\x01\x00", false), None);
+        // a character cut by the end of a preview
+        let cut = &"caf\u{e9}".as_bytes()[..4];
+        assert_eq!(as_text(cut, true), Some("caf"));
+        assert_eq!(as_text(cut, false), None);
+        assert_eq!(as_text(b"caf\xff\xfe", true), None);
+    }
+}
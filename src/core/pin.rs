@@ -0,0 +1,38 @@
+use anyhow::Result;
+use roxide::{show_error, LocalLogFile, StateLock};
+
+use super::restore::Target;
+
+/// Pins, or unpins, the trashed items whose original path matches one of `targets`.
+///
+/// Only items recorded in history can be pinned, see [`roxide::TrashLog::pinned`].
+pub fn init_pin(targets: &[String], pinned: bool) -> Result<()> {
+    let targets = targets
+        .iter()
+        .map(|arg| Ok((arg, Target::parse(arg)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let _lock = StateLock::acquire()?;
+    let mut logs = LocalLogFile::read()?;
+    for (arg, target) in &targets {
+        let mut matched = false;
+        for log in logs
+            .iter_mut()
+            .filter(|log| log.is_trashed() && target.matches(log.from()))
+        {
+            matched = true;
+            if log.pinned != pinned {
+                log.pinned = pinned;
+                println!(
+                    "{} {}",
+                    if pinned { "pinned" } else { "unpinned" },
+                    log.from().display()
+                );
+            }
+        }
+        if !matched {
+            show_error!("no trashed item matches `{}`", arg);
+        }
+    }
+    LocalLogFile::rewrite(&logs)?;
+    Ok(())
+}
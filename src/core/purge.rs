@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use roxide::{prompt_yes, show_error, StateLock, TrashLog};
+
+use super::entries::{count, human_size, trash_entries, TrashEntry};
+use super::gc::{entry_size, purge, GcReport};
+use super::history::History;
+use super::restore::Target;
+
+/// Whether `entry` is what `arg` names: its original path, a glob matching it, or its name
+/// in the trash dir.
+fn matches(entry: &TrashEntry, arg: &str, target: &Target) -> bool {
+    target.matches(&entry.original) || entry.trash_path.file_name().is_some_and(|name| name == arg)
+}
+
+/// Permanently deletes the trashed items named by `targets`, see [`matches`], and the items
+/// trashed by the operation `op`, after asking for confirmation.
+pub fn init_purge(targets: &[String], op: Option<&str>) -> Result<()> {
+    let entries = trash_entries()?;
+    let mut chosen: Vec<&TrashEntry> = Vec::new();
+    for arg in targets {
+        let target = Target::parse(arg)?;
+        let matched: Vec<&TrashEntry> = entries
+            .iter()
+            .filter(|entry| matches(entry, arg, &target))
+            .collect();
+        if matched.is_empty() {
+            show_error!("no trashed item matches `{}`", arg);
+        }
+        chosen.extend(matched);
+    }
+    if let Some(op) = op {
+        let logs: Vec<TrashLog> = entries
+            .iter()
+            .filter_map(|entry| entry.log.clone())
+            .collect();
+        let op = History::find_op(&logs, op).map_err(|err| anyhow!("{}", err))?;
+        chosen.extend(
+            entries
+                .iter()
+                .filter(|entry| entry.log.as_ref().is_some_and(|log| log.op() == &op)),
+        );
+    }
+    let mut seen = HashSet::new();
+    chosen.retain(|entry| seen.insert(&entry.trash_path));
+    if chosen.is_empty() {
+        return Ok(());
+    }
+
+    for entry in &chosen {
+        println!(
+            "  {}  {}  ({})",
+            entry.original.display(),
+            human_size(entry_size(entry)),
+            entry.trash_path.display()
+        );
+    }
+    if !prompt_yes!("permanently delete {}? (yes/y):", count(chosen.len())) {
+        return Ok(());
+    }
+
+    let chosen: HashSet<&PathBuf> = chosen.iter().map(|entry| &entry.trash_path).collect();
+    let report = purge_trash_paths(&chosen)?;
+    println!(
+        "purged {}, {}",
+        count(report.items.len()),
+        human_size(report.bytes)
+    );
+    Ok(())
+}
+
+/// Purges the items which are still in the trash at one of `chosen`.
+///
+/// They are looked up again under the lock, another process may have restored or purged some
+/// of them since they were chosen.
+pub fn purge_trash_paths(chosen: &HashSet<&PathBuf>) -> Result<GcReport> {
+    let _lock = StateLock::acquire()?;
+    let items = trash_entries()?
+        .into_iter()
+        .filter(|entry| chosen.contains(&entry.trash_path))
+        .map(|entry| {
+            let size = entry_size(&entry);
+            (entry, size)
+        });
+    purge(items, false)
+}
@@ -0,0 +1,210 @@
+use std::io;
+use std::path::{absolute, Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Local};
+use roxide::{
+    item_size, time, FileKind, HistoryArgs, LocalLogFile, LogStatus, OutputFormat, TrashLog,
+};
+use serde::Serialize;
+
+use super::entries::{count, human_size};
+use super::restore::Target;
+
+/// The filters of `roxide history`, parsed.
+#[derive(Debug, Default)]
+pub struct Filter {
+    since: Option<DateTime<Local>>,
+    until: Option<DateTime<Local>>,
+    dir: Option<PathBuf>,
+    target: Option<Target>,
+    op: Option<String>,
+    status: Option<LogStatus>,
+}
+
+impl Filter {
+    pub fn new(args: &HistoryArgs) -> Result<Self> {
+        Ok(Self {
+            since: args
+                .since
+                .as_deref()
+                .map(|since| time::parse(since, false))
+                .transpose()?,
+            until: args
+                .until
+                .as_deref()
+                .map(|until| time::parse(until, true))
+                .transpose()?,
+            dir: args.dir.as_deref().map(absolute).transpose()?,
+            target: args.glob.as_deref().map(Target::parse).transpose()?,
+            op: args.op.clone(),
+            status: args.status,
+        })
+    }
+
+    pub fn matches(&self, log: &TrashLog) -> bool {
+        self.since.is_none_or(|since| log.time >= since)
+            && self.until.is_none_or(|until| log.time <= until)
+            && self.dir.as_ref().is_none_or(|dir| log.from().starts_with(dir))
+            && self
+                .target
+                .as_ref()
+                .is_none_or(|target| target.matches(log.from()))
+            && self
+                .op
+                .as_ref()
+                .is_none_or(|op| log.op().as_str().starts_with(op.as_str()))
+            && self
+                .status
+                .is_none_or(|status| log.current_status() == status)
+    }
+}
+
+/// A history record as printed by `roxide history`.
+#[derive(Debug, Serialize)]
+struct HistoryRow<'a> {
+    op: &'a str,
+    time: DateTime<FixedOffset>,
+    status: LogStatus,
+    original_path: &'a Path,
+    trash_path: &'a Path,
+    restored_to: Option<&'a Path>,
+    file_type: Option<FileKind>,
+    /// Size in bytes, as it is in the trash or as it was when it was trashed
+    size: Option<u64>,
+}
+
+impl<'a> From<&'a TrashLog> for HistoryRow<'a> {
+    fn from(log: &'a TrashLog) -> Self {
+        let status = log.current_status();
+        Self {
+            op: log.op().as_str(),
+            time: log.time,
+            status,
+            original_path: log.from(),
+            trash_path: log.to(),
+            restored_to: log.restored_to.as_deref(),
+            file_type: log.item().map(|item| item.file_type),
+            size: match status {
+                LogStatus::Trashed => item_size(log.to()),
+                _ => log.item().map(|item| item.size),
+            },
+        }
+    }
+}
+
+/// Prints the history records matching `args`, oldest first.
+pub fn init_history(args: &HistoryArgs) -> Result<()> {
+    let filter = Filter::new(args)?;
+    let logs = LocalLogFile::read()?;
+    let rows: Vec<HistoryRow<'_>> = logs
+        .iter()
+        .filter(|log| filter.matches(log))
+        .map(HistoryRow::from)
+        .collect();
+    match args.format {
+        OutputFormat::Table => print_table(&rows),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout());
+            for row in &rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints `rows` grouped by their operation.
+fn print_table(rows: &[HistoryRow<'_>]) {
+    if rows.is_empty() {
+        println!("Nothing in history");
+        return;
+    }
+    let sizes: Vec<String> = rows
+        .iter()
+        .map(|row| row.size.map(human_size).unwrap_or_else(|| "-".to_string()))
+        .collect();
+    let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+    let mut last_op = None;
+    for (row, size) in rows.iter().zip(&sizes) {
+        if last_op != Some(row.op) {
+            let items = rows.iter().filter(|other| other.op == row.op).count();
+            println!(
+                "operation {}  {}  {}",
+                row.op,
+                row.time.format("%Y-%m-%d %H:%M:%S %:z"),
+                count(items)
+            );
+            last_op = Some(row.op);
+        }
+        let location = match (row.status, row.restored_to) {
+            (LogStatus::Reverted | LogStatus::Restored, Some(path)) => path,
+            (LogStatus::Reverted | LogStatus::Restored, None) => row.original_path,
+            _ => row.trash_path,
+        };
+        println!(
+            "  {:<8}  {:>size_width$}  {}  ->  {}",
+            row.status,
+            size,
+            row.original_path.display(),
+            location.display(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use chrono::{Duration, Local};
+    use roxide::{HistoryArgs, LogId, LogStatus, TrashLog};
+
+    use super::Filter;
+
+    fn log(from: &str) -> TrashLog {
+        TrashLog::new(
+            LogId::from(20241112214434_u64),
+            PathBuf::from(from),
+            PathBuf::from("/nonexistent/Trash/files").join(from.trim_start_matches('/')),
+        )
+    }
+
+    #[test]
+    fn filter_history() {
+        let log = log("/home/alice/src/main.rs");
+        let filter = |args: HistoryArgs| Filter::new(&args).unwrap().matches(&log);
+
+        assert!(filter(HistoryArgs::default()));
+        assert!(filter(HistoryArgs {
+            dir: Some(PathBuf::from("/home/alice")),
+            glob: Some("/home/*/src/*.rs".to_string()),
+            op: Some(log.op().as_str()[..8].to_string()),
+            since: Some("1h".to_string()),
+            until: Some(Local::now().format("%Y-%m-%d").to_string()),
+            ..Default::default()
+        }));
+        assert!(!filter(HistoryArgs {
+            dir: Some(PathBuf::from("/home/alice/sr")),
+            ..Default::default()
+        }));
+        assert!(!filter(HistoryArgs {
+            since: Some((Local::now() + Duration::hours(1)).to_rfc3339()),
+            ..Default::default()
+        }));
+        assert!(!filter(HistoryArgs {
+            op: Some("x".to_string()),
+            ..Default::default()
+        }));
+
+        // the trash path doesn't exist, so it was purged
+        assert_eq!(log.current_status(), LogStatus::Purged);
+        assert!(filter(HistoryArgs {
+            status: Some(LogStatus::Purged),
+            ..Default::default()
+        }));
+        let restored = log.clone().restored(&PathBuf::from("/home/alice/src/main.rs"));
+        assert_eq!(restored.current_status(), LogStatus::Restored);
+    }
+}
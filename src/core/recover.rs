@@ -0,0 +1,99 @@
+use std::io;
+use std::path::Path;
+
+use anyhow::Result;
+use log::debug;
+use roxide::{show_error, Journal, JournalStage, LocalLogFile, MoveKind, StateLock, Unfinished};
+
+use super::copy::remove_all;
+use super::history::remove_trash_info;
+
+/// What was done about an interrupted move.
+#[derive(Debug, PartialEq, Eq)]
+enum Recovery {
+    /// The item made it into the trash, its history record is written
+    Replayed,
+    /// The item is where it was, anything left in the trash dir is removed
+    RolledBack,
+}
+
+/// Finishes or undoes every move into the trash which was interrupted, see [`Journal`].
+///
+/// Runs at the start of every invocation, it only reads the journal if it isn't empty.
+/// Moves which are still in progress in another process are left alone, the journal is only
+/// emptied once there are none.
+pub fn recover() -> Result<()> {
+    if Journal::is_empty() {
+        return Ok(());
+    }
+    let _lock = StateLock::acquire()?;
+    let (running, unfinished): (Vec<Unfinished>, Vec<Unfinished>) = Journal::unfinished()?
+        .into_iter()
+        .partition(Unfinished::in_progress);
+    if !unfinished.is_empty() {
+        let mut logs = LocalLogFile::read()?;
+        let mut recovered = Vec::new();
+        for entry in unfinished {
+            recovered.push(entry.id.clone());
+            match recover_entry(&entry)? {
+                Recovery::Replayed => {
+                    show_error!(
+                        "recovered interrupted removal of `{}`, it is in {}",
+                        entry.log.from().display(),
+                        entry.log.to().display()
+                    );
+                    match logs.iter_mut().find(|log| log.same_record(&entry.log)) {
+                        Some(log) => *log = entry.log,
+                        None => logs.push(entry.log),
+                    }
+                }
+                Recovery::RolledBack => show_error!(
+                    "rolled back interrupted removal of `{}`",
+                    entry.source.display()
+                ),
+            }
+        }
+        LocalLogFile::rewrite(&logs)?;
+        if !running.is_empty() {
+            for id in recovered {
+                Journal::recovered(&id)?;
+            }
+        }
+    }
+    match running.is_empty() {
+        true => Journal::clear(),
+        false => Ok(()),
+    }
+}
+
+/// Works out from the filesystem how far the move of `entry` got.
+///
+/// A rename either happened or not: the trash name was free when the move began, so if it
+/// exists the rename happened, and anything at the source path was created since.
+/// Only a copy can leave the item in both places. It's only finished if the copy was verified,
+/// otherwise the copy is removed.
+fn recover_entry(entry: &Unfinished) -> io::Result<Recovery> {
+    let source = &entry.source;
+    let target = entry.log.to();
+    debug!("recovering {:?}", entry);
+    match (exists(source), exists(target), entry.kind, entry.stage) {
+        (_, true, MoveKind::Rename, _) | (false, true, MoveKind::Copy, _) => Ok(Recovery::Replayed),
+        (true, true, MoveKind::Copy, JournalStage::Copied) => {
+            remove_all(source)?;
+            Ok(Recovery::Replayed)
+        }
+        (true, true, MoveKind::Copy, JournalStage::Pending) => {
+            remove_all(target)?;
+            remove_trash_info(target);
+            Ok(Recovery::RolledBack)
+        }
+        (_, false, _, _) => {
+            remove_trash_info(target);
+            Ok(Recovery::RolledBack)
+        }
+    }
+}
+
+fn exists(path: &Path) -> bool {
+    path.symlink_metadata().is_ok()
+}
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{absolute, Path, PathBuf};
+
+use anyhow::Result;
+use roxide::{show_error, ConflictPolicy, StateLock, TrashLog};
+
+use super::conflict::RestoreAction;
+use super::entries::{trash_entries, TrashEntry};
+use super::history::History;
+
+/// What to restore, given as argument to `roxide restore`.
+#[derive(Debug)]
+pub enum Target {
+    /// The original path of a trashed item
+    Path(PathBuf),
+    /// A glob pattern matching original paths
+    Glob(glob::Pattern),
+}
+
+impl Target {
+    /// Relative paths and patterns are relative to the current directory.
+    pub fn parse(arg: &str) -> Result<Self> {
+        let path = absolute(arg)?;
+        if arg.contains(['*', '?', '[']) {
+            Ok(Target::Glob(glob::Pattern::new(&path.to_string_lossy())?))
+        } else {
+            Ok(Target::Path(path))
+        }
+    }
+
+    pub fn matches(&self, original: &Path) -> bool {
+        match self {
+            Target::Path(path) => path == original,
+            Target::Glob(pattern) => pattern.matches_path_with(
+                original,
+                glob::MatchOptions {
+                    require_literal_separator: true,
+                    ..Default::default()
+                },
+            ),
+        }
+    }
+}
+
+/// Restores the trashed items whose original path matches one of `targets`.
+///
+/// If an original path was trashed more than once, the candidates are listed newest first and
+/// the user is asked which one to restore, unless `newest` is set.
+pub fn init_restore(
+    targets: &[String],
+    newest: bool,
+    policy: ConflictPolicy,
+    to: Option<&Path>,
+) -> Result<()> {
+    let entries = trash_entries()?;
+    let mut chosen = Vec::new();
+    for arg in targets {
+        let target = Target::parse(arg)?;
+        let mut by_path: BTreeMap<&Path, Vec<&TrashEntry>> = BTreeMap::new();
+        for entry in entries
+            .iter()
+            .filter(|entry| target.matches(&entry.original))
+        {
+            by_path.entry(&entry.original).or_default().push(entry);
+        }
+        if by_path.is_empty() {
+            show_error!("no trashed item matches `{}`", arg);
+            continue;
+        }
+        for (original, mut candidates) in by_path {
+            // entries are oldest first
+            candidates.reverse();
+            let pick = if candidates.len() == 1 || newest {
+                Some(0)
+            } else {
+                choose(original, &candidates)?
+            };
+            if let Some(index) = pick {
+                chosen.push(candidates[index].clone());
+            }
+        }
+    }
+    restore_entries(&chosen, policy, to)
+}
+
+/// Lists the candidates for `original`, newest first, and asks which one to restore.
+fn choose(original: &Path, candidates: &[&TrashEntry]) -> Result<Option<usize>> {
+    println!(
+        "roxide: `{}` was trashed {} times:",
+        original.display(),
+        candidates.len()
+    );
+    for (index, entry) in candidates.iter().enumerate() {
+        println!(
+            "  {}) {}  {}",
+            index + 1,
+            entry.time.format("%Y-%m-%d %H:%M:%S %:z"),
+            entry.trash_path.display()
+        );
+    }
+    loop {
+        print!(
+            "roxide: restore which one? [1-{}, default 1, 0 to skip] ",
+            candidates.len()
+        );
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        match input.trim() {
+            "" => return Ok(Some(0)),
+            "0" => return Ok(None),
+            choice => match choice.parse::<usize>() {
+                Ok(n) if (1..=candidates.len()).contains(&n) => return Ok(Some(n - 1)),
+                _ => show_error!("invalid choice: `{}`", choice),
+            },
+        }
+    }
+}
+
+/// Moves `entries` back to their original paths, or to `to` if given, see [`History::destinations`],
+/// and marks them as restored in history.
+pub fn restore_entries(
+    entries: &[TrashEntry],
+    policy: ConflictPolicy,
+    to: Option<&Path>,
+) -> Result<()> {
+    let _lock = StateLock::acquire()?;
+    let targets = match to {
+        Some(to) => History::destinations(
+            &entries
+                .iter()
+                .map(|entry| {
+                    let relative = entry
+                        .log
+                        .as_ref()
+                        .and_then(|log| log.relative_path.as_deref());
+                    (entry.original.as_path(), relative)
+                })
+                .collect::<Vec<_>>(),
+            to,
+        ),
+        None => entries.iter().map(|entry| entry.original.clone()).collect(),
+    };
+    let mut restored = Vec::new();
+    for (entry, target) in entries.iter().zip(&targets) {
+        let item = entry.log.as_ref().and_then(TrashLog::item);
+        match History::revert(&entry.trash_path, target, item, policy) {
+            Ok(report) => {
+                println!("{}", report);
+                if report.is_done() && report.action != RestoreAction::Missing {
+                    restored.push((entry.trash_path.clone(), report.path));
+                }
+            }
+            Err(err) => show_error!("failed to restore `{}`: {}", entry.original.display(), err),
+        }
+    }
+    History::mark_restored(&restored)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::Target;
+
+    #[test]
+    fn target_matches() {
+        let target = Target::parse("/home/alice/src/foo.rs").unwrap();
+        assert!(target.matches(Path::new("/home/alice/src/foo.rs")));
+        assert!(!target.matches(Path::new("/home/alice/src/foo.rs.bak")));
+
+        let target = Target::parse("/home/alice/src/*.rs").unwrap();
+        assert!(target.matches(Path::new("/home/alice/src/foo.rs")));
+        assert!(!target.matches(Path::new("/home/alice/foo.rs")));
+
+        let target = Target::parse("foo.rs").unwrap();
+        assert!(target.matches(&std::env::current_dir().unwrap().join("foo.rs")));
+    }
+}
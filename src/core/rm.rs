@@ -1,26 +1,28 @@
 #![allow(unused_labels)]
 
-use std::env::current_dir;
-use std::fs::{self, remove_dir, File};
+use std::fs::{self, remove_dir};
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::{absolute, Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use log::*;
 use roxide::filter::PathFilter;
 use roxide::{
-    check_cross_device, check_root, prompt_yes, show_error, trash_dir, verbose, Cli, ConfigFile, InteractiveMode
+    check_cross_device, check_root, prompt_yes, show_error, verbose, Cli, ConfigFile,
+    InteractiveMode, ItemMeta, Journal, JournalEntry, LocalLogFile, LogId, MoveKind, OpId, StateLock,
+    TrashDir, TrashLog, TrashMeta,
 };
 
-use crate::core::history::{History, LogId, TrashMeta};
+use crate::core::history::History;
 
 use roxide::RoxError as Error;
 
-use super::history::Trash;
+use super::history::{show_not_removed, Trash};
 
 pub type RoError<'a, T> = Result<T, Error>;
 
-fn init_checks(item: &Path) -> RoError<()> {
+fn init_checks(item: &Path) -> RoError<'_, ()> {
     if item.parent().is_none() && item.has_root() {
         return Err(Error::IsRoot(item.to_path_buf()));
     }
@@ -53,11 +55,173 @@ fn init_force_remove_without_prompt(item: &Path) {
     }
 }
 
-fn core_remove(args: &Cli, item: &Path) {
-    let trash = Trash { file: item };
+/// Returns the trash dir `item_path` should be moved to.
+///
+/// Items on the same device as the home trash go there, items on other devices
+/// go to the trash dir of their own mount point. If there is no usable trash dir
+/// on that device, the home trash is returned and moving the item will fail with
+/// `CrossesDevices`, which is handled by [`copy_to_trash_with_prompt`].
+fn resolve_trash_dir(item_path: &Path) -> TrashDir {
+    let home = TrashDir::home().unwrap();
+    match check_cross_device(item_path) {
+        Ok(()) => home,
+        Err(err) => {
+            debug!("{}", err);
+            TrashDir::for_mount(item_path).unwrap_or(home)
+        }
+    }
+}
+
+/// Number of items recorded in history by this invocation.
+static TRASHED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of items this invocation trashed so far.
+pub fn trashed_count() -> usize {
+    TRASHED.load(Ordering::Relaxed)
+}
+
+/// The history record of `item`, which is about to be moved to `trash_path`.
+fn history_record(
+    args: &Cli,
+    item: &Path,
+    log_id: &str,
+    item_path: PathBuf,
+    trash_path: PathBuf,
+    item_meta: Option<ItemMeta>,
+) -> TrashLog {
+    History {
+        log_id: LogId::from_str(log_id).unwrap(),
+        metadata: TrashMeta {
+            file_path: item_path,
+            trash_path,
+            item: item_meta,
+        },
+        relative_path: relative_to_root(args, item),
+    }
+    .record()
+}
+
+/// Records a trashed item in history and commits its journal entry.
+///
+/// If the history can't be written, the journal entry is left uncommitted,
+/// so the record is written on the next run, see [`recover`](super::recover::recover).
+fn trashed(args: &Cli, item: &Path, log: &TrashLog, entry: JournalEntry) -> anyhow::Result<()> {
+    verbose!(
+        args.verbose,
+        "Trashed {} to {}",
+        item.display(),
+        log.to().display()
+    );
+    // a copy from another device runs without the lock
+    let _lock = StateLock::acquire()?;
+    LocalLogFile::write(log)?;
+    TRASHED.fetch_add(1, Ordering::Relaxed);
+    entry.commit()
+}
+
+/// Reports an item which was trashed but couldn't be recorded, see [`trashed`].
+fn show_unrecorded(item: &Path, err: anyhow::Error) {
+    show_error!(
+        "`{}` was trashed but not recorded in history: {}",
+        item.display(),
+        err
+    );
+}
+
+/// Metadata of the item at `item_path`, read before it is moved and outside of the lock,
+/// since regular files are hashed.
+fn capture_meta(item_path: &Path) -> Option<ItemMeta> {
+    ItemMeta::capture(item_path)
+        .inspect_err(|err| warn!("can't read metadata of {}: {}", item_path.display(), err))
+        .ok()
+}
+
+/// Writes the journal entry of moving `item_path` to the trash as `log`.
+fn begin_move(
+    item: &Path,
+    item_path: &Path,
+    log: &TrashLog,
+    kind: MoveKind,
+) -> Option<JournalEntry> {
+    match Journal::begin(item_path, log, kind) {
+        Ok(entry) => Some(entry),
+        Err(err) => {
+            show_error!("can't remove `{}`: {}", item.display(), err);
+            None
+        }
+    }
+}
+
+/// Path of an item found by a pattern removal, relative to the directory argument it was found in.
+///
+/// `roxide docs -rp .pdf` records `docs/a/x.pdf` as `a/x.pdf`.
+fn relative_to_root(args: &Cli, item: &Path) -> Option<PathBuf> {
+    args.pattern.as_ref()?;
+    args.file
+        .as_ref()?
+        .iter()
+        .filter(|root| item != *root)
+        .filter_map(|root| item.strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count())
+        .map(Path::to_path_buf)
+}
+
+/// Offers to copy an item, which can't be moved to the trash dir since it is on another device,
+/// into the home trash and remove the original once the copy is verified.
+fn copy_to_trash_with_prompt(args: &Cli, item: &Path, item_path: PathBuf) {
+    let trash = Trash {
+        file: item,
+        dir: TrashDir::home().unwrap(),
+    };
+    if !prompt_yes!(
+        "`{}` is on a different device. copy it to the trash and remove the original?",
+        item.display()
+    ) {
+        init_force_remove_with_prompt(item);
+        return;
+    }
+    let item_meta = capture_meta(&item_path);
+    // only held until the name is reserved, see [`Trash::copy_to_trash`]
+    let lock = match StateLock::acquire() {
+        Ok(lock) => lock,
+        Err(err) => {
+            show_error!("can't copy `{}` to trash: {}", item.display(), err);
+            return;
+        }
+    };
     let id = trash.get_log_id();
-    let item_path = current_dir().unwrap().join(item);
-    let trash_path = trash_dir().unwrap().join(trash.trash_name(id.1));
+    let trash_name = trash.trash_name(id.1);
+    let log = history_record(
+        args,
+        item,
+        &id.0,
+        item_path.clone(),
+        trash.dir.files().join(&trash_name),
+        item_meta,
+    );
+    let Some(entry) = begin_move(item, &item_path, &log, MoveKind::Copy) else {
+        return;
+    };
+    match trash.copy_to_trash(&item_path, &trash_name, lock, || {
+        entry.copied().map_err(io::Error::other)
+    }) {
+        Ok(copied) => {
+            if let Err(err) = trashed(args, item, &log, entry) {
+                show_unrecorded(item, err);
+            }
+            if let Err(err) = copied.removed {
+                show_not_removed(&item_path, &err);
+            }
+        }
+        Err(err) => {
+            let _ = entry.abort();
+            show_error!("failed to copy `{}` to trash: {}", item.display(), err)
+        }
+    }
+}
+
+fn core_remove(args: &Cli, item: &Path) {
+    let item_path = absolute(item).unwrap();
 
     let config = ConfigFile::get_config().unwrap();
 
@@ -68,55 +232,65 @@ fn core_remove(args: &Cli, item: &Path) {
     } else {
         trace!("is normal user");
 
-        // we can't move items from an another device.
-        // only option is to copy or delete
-        // So. we will prompt for force remove
-        match check_cross_device(&item_path) {
-            Ok(()) => match config.settings.check_sha256 {
-                Some(true) if trash.compute_sha256(args) && item.is_file() => {
-                    init_force_remove_without_prompt(&item_path);
-                    verbose!(
-                        args.verbose,
-                        "roxide: removed {} permanently",
-                        &item_path.display()
-                    );
-                }
-                _ => {
-                    let rename_result = fs::rename(
-                        &item_path,
-                        trash_dir()
-                            .unwrap()
-                            .join(trash.trash_name(trash.get_log_id().1)),
-                    );
-                    match rename_result {
-                        Ok(_) => {
-                            if args.pattern.is_none() {
-                                verbose!(
-                                    args.verbose,
-                                    "Trashed {} to {}",
-                                    item.display(),
-                                    trash_dir()
-                                        .unwrap()
-                                        .join(trash.trash_name(trash.get_log_id().1))
-                                        .display()
-                                );
-                                let history = History {
-                                    log_id: LogId::from_str(id.0.to_string().as_str()).unwrap(),
-                                    metadata: TrashMeta {
-                                        file_path: item_path,
-                                        trash_path,
-                                    },
-                                };
-                                History::write(history).unwrap();
-                            }
+        let trash = Trash {
+            file: item,
+            dir: resolve_trash_dir(&item_path),
+        };
+        let item_meta = capture_meta(&item_path);
+        // held until the item is recorded in history, released before prompting
+        let lock = match StateLock::acquire() {
+            Ok(lock) => lock,
+            Err(err) => {
+                show_error!("can't remove `{}`: {}", item.display(), err);
+                return;
+            }
+        };
+        let id = trash.get_log_id();
+        let trash_name = trash.trash_name(id.1);
+        match config.settings.check_sha256 {
+            Some(true) if trash.compute_sha256(args) && item.is_file() => {
+                drop(lock);
+                init_force_remove_without_prompt(&item_path);
+                verbose!(
+                    args.verbose,
+                    "roxide: removed {} permanently",
+                    &item_path.display()
+                );
+            }
+            _ => {
+                let log = history_record(
+                    args,
+                    item,
+                    &id.0,
+                    item_path.clone(),
+                    trash.dir.files().join(&trash_name),
+                    item_meta,
+                );
+                let Some(entry) = begin_move(item, &item_path, &log, MoveKind::Rename) else {
+                    return;
+                };
+                let rename_result = trash.move_to_trash(&item_path, &trash_name);
+                match rename_result {
+                    Ok(_) => {
+                        if let Err(err) = trashed(args, item, &log, entry) {
+                            show_unrecorded(item, err);
                         }
-                        Err(err) => match err.kind() {
+                    }
+                    Err(err) => {
+                        let _ = entry.abort();
+                        drop(lock);
+                        match err.kind() {
                             io::ErrorKind::PermissionDenied => {
                                 show_error!(
                                     "Don't have enough permission to remove `{}`.",
                                     item.display()
                                 );
                             }
+                            // we can't move items from an another device.
+                            // only option is to copy or delete
+                            io::ErrorKind::CrossesDevices => {
+                                copy_to_trash_with_prompt(args, item, item_path);
+                            }
                             // NOTE: unstable
                             // io::ErrorKind::ResourceBusy => {
                             //     show_error!(
@@ -135,19 +309,15 @@ fn core_remove(args: &Cli, item: &Path) {
                                 println!("Error: {}", err);
                                 init_force_remove_with_prompt(item);
                             }
-                        },
+                        }
                     }
                 }
-            },
-            Err(err) => {
-                show_error!("{}", err);
-                init_force_remove_with_prompt(item);
             }
         }
     }
 }
 
-pub fn init_remove(items: Vec<PathBuf>, args: &Cli) -> RoError<()> {
+pub fn init_remove(items: Vec<PathBuf>, args: &Cli) -> RoError<'_, ()> {
     let entries = match PathFilter::filter(items, args) {
         Ok(filtered) => filtered,
         Err(e) => {
@@ -166,6 +336,18 @@ pub fn init_remove(items: Vec<PathBuf>, args: &Cli) -> RoError<()> {
             handle_interactive(args, item)
         }
     }
+    if TRASHED.load(Ordering::Relaxed) > 0 {
+        if let Err(err) = History::clear_redo() {
+            show_error!("failed to update reverted items in history: {}", err);
+        }
+        let op = OpId::current();
+        verbose!(
+            args.verbose,
+            "roxide: operation {}, undo it with `roxide revert --op {}`",
+            op,
+            op
+        );
+    }
     trace!("{:#?}", entries);
     Ok(())
 }
@@ -210,7 +392,7 @@ fn handle_interactive(args: &Cli, item: &Path) {
     // File::open(path) doesn't open the file in write mode
     // So, we need to use file options to open it in write mode to check if we have write permission
     #[cfg(feature = "extra_commands")]
-    let file_write_permission = File::options().read(true).write(true).open(item).is_ok();
+    let file_write_permission = fs::File::options().read(true).write(true).open(item).is_ok();
     // not including InteractiveMode::once and InteractiveMode::Never here
     match args.interactive {
         Some(InteractiveMode::Always) => {
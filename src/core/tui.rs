@@ -0,0 +1,479 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use roxide::ConflictPolicy;
+
+use super::entries::{count, human_size, trash_entries, TrashEntry};
+use super::gc::entry_size;
+use super::peek::as_text;
+use super::purge::purge_trash_paths;
+use super::restore::restore_entries;
+
+/// How much of a file the preview reads.
+const PREVIEW_BYTES: u64 = 64 * 1024;
+/// How many entries of a directory the preview lists.
+const PREVIEW_ENTRIES: usize = 200;
+
+/// A trashed item as listed by the interface.
+struct Item {
+    entry: TrashEntry,
+    size: u64,
+    /// The original path, as shown and searched
+    label: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    Search,
+    ConfirmPurge,
+}
+
+/// What the user asked for. Restoring and purging happen outside of the full screen interface,
+/// so what they print stays in the terminal.
+enum Action {
+    Quit,
+    Restore,
+    Purge,
+}
+
+struct App {
+    /// Newest first
+    items: Vec<Item>,
+    /// Indexes in `items` of the items matching `query`, best match first
+    visible: Vec<usize>,
+    /// Trash paths of the selected items
+    selected: HashSet<PathBuf>,
+    query: String,
+    mode: Mode,
+    table: TableState,
+    /// Rows of the list at the last draw, to move by a page
+    page: usize,
+    /// Shown in the status line until the next key
+    message: Option<String>,
+}
+
+impl App {
+    fn new() -> Result<Self> {
+        let mut app = Self {
+            items: Vec::new(),
+            visible: Vec::new(),
+            selected: HashSet::new(),
+            query: String::new(),
+            mode: Mode::Browse,
+            table: TableState::default(),
+            page: 10,
+            message: None,
+        };
+        app.reload()?;
+        Ok(app)
+    }
+
+    /// Reads the trash again, see [`trash_entries`], forgetting the selected items which are gone.
+    fn reload(&mut self) -> Result<()> {
+        self.items = trash_entries()?
+            .into_iter()
+            .rev()
+            .map(|entry| Item {
+                size: entry_size(&entry),
+                label: entry.original.display().to_string(),
+                entry,
+            })
+            .collect();
+        let present: HashSet<&PathBuf> = self
+            .items
+            .iter()
+            .map(|item| &item.entry.trash_path)
+            .collect();
+        self.selected.retain(|path| present.contains(path));
+        self.filter();
+        Ok(())
+    }
+
+    /// Lists the items matching the query, see [`fuzzy_score`].
+    fn filter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| Some((fuzzy_score(&self.query, &item.label)?, index)))
+            .collect();
+        // stable, items which match as well stay newest first
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+        self.visible = scored.into_iter().map(|(_, index)| index).collect();
+        self.table.select(match self.visible.len() {
+            0 => None,
+            len => Some(self.table.selected().unwrap_or(0).min(len - 1)),
+        });
+    }
+
+    fn current(&self) -> Option<&Item> {
+        self.table
+            .selected()
+            .and_then(|row| self.visible.get(row))
+            .map(|index| &self.items[*index])
+    }
+
+    /// The selected items, or the current one if none is selected.
+    fn chosen(&self) -> Vec<TrashEntry> {
+        if self.selected.is_empty() {
+            return self
+                .current()
+                .map(|item| item.entry.clone())
+                .into_iter()
+                .collect();
+        }
+        self.items
+            .iter()
+            .filter(|item| self.selected.contains(&item.entry.trash_path))
+            .map(|item| item.entry.clone())
+            .collect()
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if let Some(row) = self.table.selected() {
+            let last = self.visible.len().saturating_sub(1);
+            self.table
+                .select(Some(row.saturating_add_signed(delta).min(last)));
+        }
+    }
+
+    fn toggle(&mut self, path: &Path) {
+        if !self.selected.remove(path) {
+            self.selected.insert(path.to_path_buf());
+        }
+    }
+
+    /// Selects every listed item, or unselects them if they all are.
+    fn toggle_all(&mut self) {
+        let paths: Vec<PathBuf> = self
+            .visible
+            .iter()
+            .map(|index| self.items[*index].entry.trash_path.clone())
+            .collect();
+        if paths.iter().all(|path| self.selected.contains(path)) {
+            for path in &paths {
+                self.selected.remove(path);
+            }
+        } else {
+            self.selected.extend(paths);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        self.message = None;
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Action::Quit);
+        }
+        match self.mode {
+            Mode::Search => match key.code {
+                KeyCode::Esc => {
+                    self.query.clear();
+                    self.filter();
+                    self.mode = Mode::Browse;
+                }
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.filter();
+                }
+                KeyCode::Up => self.move_by(-1),
+                KeyCode::Down => self.move_by(1),
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.filter();
+                }
+                _ => {}
+            },
+            Mode::ConfirmPurge => {
+                self.mode = Mode::Browse;
+                if matches!(key.code, KeyCode::Char('y' | 'Y')) {
+                    return Some(Action::Purge);
+                }
+            }
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+                KeyCode::Char('/') => self.mode = Mode::Search,
+                KeyCode::Char('j') | KeyCode::Down => self.move_by(1),
+                KeyCode::Char('k') | KeyCode::Up => self.move_by(-1),
+                KeyCode::PageDown => self.move_by(self.page as isize),
+                KeyCode::PageUp => self.move_by(-(self.page as isize)),
+                KeyCode::Char('g') | KeyCode::Home => self.move_by(isize::MIN),
+                KeyCode::Char('G') | KeyCode::End => self.move_by(isize::MAX),
+                KeyCode::Char(' ') => {
+                    if let Some(path) = self.current().map(|item| item.entry.trash_path.clone()) {
+                        self.toggle(&path);
+                        self.move_by(1);
+                    }
+                }
+                KeyCode::Char('a') => self.toggle_all(),
+                KeyCode::Char('r') if !self.chosen().is_empty() => return Some(Action::Restore),
+                KeyCode::Char('p') if !self.chosen().is_empty() => self.mode = Mode::ConfirmPurge,
+                _ => {}
+            },
+        }
+        None
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Action> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    if let Some(action) = self.handle_key(key) {
+                        return Ok(action);
+                    }
+                }
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search_area, main_area, status_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .areas(main_area);
+
+        let search =
+            Paragraph::new(format!("/{}", self.query)).block(Block::bordered().title(" search "));
+        frame.render_widget(search, search_area);
+        if self.mode == Mode::Search {
+            frame.set_cursor_position((
+                search_area.x + 2 + self.query.chars().count() as u16,
+                search_area.y + 1,
+            ));
+        }
+
+        let rows = self.visible.iter().map(|index| {
+            let item = &self.items[*index];
+            let mark = match self.selected.contains(&item.entry.trash_path) {
+                true => "*",
+                false => " ",
+            };
+            let original = match item.entry.log.as_ref().is_some_and(|log| log.pinned) {
+                true => format!("{} (pinned)", item.label),
+                false => item.label.clone(),
+            };
+            Row::new([
+                Cell::from(mark),
+                Cell::from(item.entry.time.format("%Y-%m-%d %H:%M").to_string()),
+                Cell::from(Line::from(human_size(item.size)).right_aligned()),
+                Cell::from(original),
+            ])
+        });
+        let title = format!(
+            " trash: {} of {} items, {} selected ",
+            self.visible.len(),
+            self.items.len(),
+            self.selected.len()
+        );
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(1),
+                Constraint::Length(16),
+                Constraint::Length(10),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["", "DELETED", "      SIZE", "ORIGINAL PATH"]).bold())
+        .block(Block::bordered().title(title))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        // borders and header
+        self.page = usize::from(list_area.height.saturating_sub(3)).max(1);
+        frame.render_stateful_widget(table, list_area, &mut self.table);
+
+        let preview = self
+            .current()
+            .map(|item| preview(&item.entry.trash_path))
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(preview).block(Block::bordered().title(" preview ")),
+            preview_area,
+        );
+
+        let status = match (&self.mode, &self.message) {
+            (Mode::ConfirmPurge, _) => Line::from(format!(
+                "permanently delete {}? (y/n)",
+                count(self.chosen().len())
+            ))
+            .red()
+            .bold(),
+            (Mode::Search, _) => Line::from("type to search  enter: done  esc: clear"),
+            (Mode::Browse, Some(message)) => Line::from(message.as_str()).bold(),
+            (Mode::Browse, None) => {
+                Line::from("/: search  space: select  a: select all  r: restore  p: purge  q: quit")
+            }
+        };
+        frame.render_widget(status, status_area);
+    }
+}
+
+/// Scores how well `query` matches `text` as a subsequence of its characters, `None` if it doesn't.
+///
+/// The match is case insensitive unless `query` has an uppercase letter. Consecutive characters,
+/// characters at the start of a word and characters in the file name score higher.
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let normalize = |c: char| match case_sensitive {
+        true => c,
+        false => c.to_lowercase().next().unwrap_or(c),
+    };
+    let mut query = query.chars().map(normalize).peekable();
+    let name_start = text.rfind('/').map_or(0, |index| index + 1);
+    let mut score = 0;
+    let mut previous = '/';
+    let mut previous_matched = false;
+    for (index, c) in text.char_indices() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        let matched = normalize(c) == wanted;
+        if matched {
+            query.next();
+            score += 1;
+            if previous_matched {
+                score += 4;
+            }
+            if !previous.is_alphanumeric() {
+                score += 3;
+            }
+            if index >= name_start {
+                score += 2;
+            }
+        }
+        previous = c;
+        previous_matched = matched;
+    }
+    query.peek().is_none().then_some(score)
+}
+
+/// The start of a trashed file if it is text, the entries of a trashed directory,
+/// or a description of anything else.
+fn preview(path: &Path) -> Text<'static> {
+    let meta = match path.symlink_metadata() {
+        Ok(meta) => meta,
+        Err(err) => return Text::from(format!("can't read `{}`: {}", path.display(), err)),
+    };
+    if meta.is_symlink() {
+        return match fs::read_link(path) {
+            Ok(target) => Text::from(format!("symlink to {}", target.display())),
+            Err(err) => Text::from(format!("can't read symlink: {}", err)),
+        };
+    }
+    if meta.is_dir() {
+        let mut names: Vec<String> = match fs::read_dir(path) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    match entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                        true => name + "/",
+                        false => name,
+                    }
+                })
+                .collect(),
+            Err(err) => return Text::from(format!("can't read directory: {}", err)),
+        };
+        names.sort();
+        let more = names.len().saturating_sub(PREVIEW_ENTRIES);
+        let mut lines: Vec<Line> = names
+            .into_iter()
+            .take(PREVIEW_ENTRIES)
+            .map(Line::from)
+            .collect();
+        if more > 0 {
+            lines.push(Line::from(format!("... and {} more", more)).italic());
+        }
+        return Text::from(lines);
+    }
+    if !meta.is_file() {
+        return Text::from("not a regular file");
+    }
+    let mut bytes = Vec::new();
+    if let Err(err) =
+        File::open(path).and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut bytes))
+    {
+        return Text::from(format!("can't read file: {}", err));
+    }
+    match as_text(&bytes, bytes.len() as u64 == PREVIEW_BYTES) {
+        Some(text) => Text::from(text.replace('\t', "    ")),
+        None => Text::from(format!("binary file, {}", human_size(meta.len()))).italic(),
+    }
+}
+
+/// `roxide tui`, browses the items in the trash dirs like `roxide list`, and restores or purges
+/// them like `roxide restore` and `roxide purge`.
+pub fn init_tui(policy: ConflictPolicy) -> Result<()> {
+    if !io::stdout().is_terminal() {
+        return Err(anyhow!("`roxide tui` needs a terminal"));
+    }
+    let mut app = App::new()?;
+    loop {
+        let mut terminal = ratatui::try_init()?;
+        let action = app.run(&mut terminal);
+        ratatui::try_restore()?;
+        let chosen = app.chosen();
+        app.message = Some(match action? {
+            Action::Quit => return Ok(()),
+            Action::Restore => match restore_entries(&chosen, policy, None) {
+                Ok(()) => {
+                    let restored = chosen
+                        .iter()
+                        .filter(|entry| entry.trash_path.symlink_metadata().is_err())
+                        .count();
+                    format!("restored {} of {}", restored, count(chosen.len()))
+                }
+                Err(err) => format!("restore failed: {}", err),
+            },
+            Action::Purge => {
+                let paths: HashSet<&PathBuf> =
+                    chosen.iter().map(|entry| &entry.trash_path).collect();
+                match purge_trash_paths(&paths) {
+                    Ok(report) => format!(
+                        "purged {}, {}",
+                        count(report.items.len()),
+                        human_size(report.bytes)
+                    ),
+                    Err(err) => format!("purge failed: {}", err),
+                }
+            }
+        });
+        app.selected.clear();
+        app.reload()?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::fuzzy_score;
+
+    #[test]
+    fn fuzzy_match() {
+        assert_eq!(fuzzy_score("", "/home/alice/notes.txt"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "/home/alice/notes.txt"), None);
+        assert!(fuzzy_score("nts", "/home/alice/notes.txt").is_some());
+        // smart case
+        assert!(fuzzy_score("notes", "/home/alice/NOTES.txt").is_some());
+        assert_eq!(fuzzy_score("Notes", "/home/alice/notes.txt"), None);
+
+        let score = |text| fuzzy_score("notes", text).unwrap();
+        assert!(score("/home/alice/notes.txt") > score("/home/alice/n/o/t/e/s.txt"));
+        assert!(score("/home/alice/notes.txt") > score("/home/alice/notes/todo.txt"));
+    }
+}
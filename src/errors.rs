@@ -31,6 +31,8 @@ pub enum RoxError {
     #[error(transparent)]
     #[diagnostic(code(roxide::any_error))]
     AnyError(#[from] anyhow::Error),
+    #[error("roxide: `{path}` is locked by {holder}, gave up waiting for it")]
+    Locked { path: PathBuf, holder: String },
     #[error("Error: can't find cache dir")]
     CantFindCacheDir,
     #[error("Error: can't find config dir")]
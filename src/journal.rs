@@ -0,0 +1,263 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anyhow::Error;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::lock::process_exists;
+use crate::{LocalDataDir, OpId, TrashLog};
+
+/// # Journal write-ahead log of moves into the trash
+///
+/// Before an item is moved into a trash dir, the history record it will get is written to
+/// `$XDG_DATA_HOME/roxide/journal.jsonl` as pending. Once the record is in the history
+/// the entry is marked done. An entry which is still pending on the next start was interrupted,
+/// by a crash or power loss, and is replayed or rolled back from the state of the filesystem.
+///
+/// Entries are begun while holding the [`crate::StateLock`]. Copying an item from another
+/// device runs without it, so a pending entry seen while holding the lock belongs to a process
+/// which is gone, or which is still copying, see [`Unfinished::in_progress`].
+pub struct Journal;
+
+/// A single line of the journal.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum JournalRecord {
+    /// `source` is about to be moved to the trash path of `log`.
+    Pending {
+        id: String,
+        source: PathBuf,
+        log: Box<TrashLog>,
+        /// Older versions didn't record it, their moves are treated as copies
+        #[serde(default)]
+        kind: MoveKind,
+    },
+    /// `source` was copied into the trash and the copy verified, it is about to be removed.
+    Copied { id: String },
+    /// The move is recorded in history.
+    Done { id: String },
+    /// The move failed, nothing changed.
+    Aborted { id: String },
+}
+
+/// How an item is moved into the trash.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveKind {
+    /// Renamed into a trash dir on the same device, which either happened or not
+    Rename,
+    /// Copied from another device, the original is removed once the copy is verified
+    #[default]
+    Copy,
+}
+
+/// Where an interrupted move got to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalStage {
+    Pending,
+    Copied,
+}
+
+/// An interrupted move, see [`Journal::unfinished`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unfinished {
+    pub id: String,
+    pub source: PathBuf,
+    pub log: TrashLog,
+    pub kind: MoveKind,
+    pub stage: JournalStage,
+}
+
+/// A move which was started with [`Journal::begin`].
+#[must_use = "an entry which isn't committed or aborted is recovered on the next start"]
+#[derive(Debug)]
+pub struct JournalEntry {
+    id: String,
+}
+
+impl Journal {
+    /// The journal file, `$XDG_DATA_HOME/roxide/journal.jsonl`.
+    pub fn path() -> Result<PathBuf, Error> {
+        Ok(LocalDataDir::new()?.join("journal.jsonl"))
+    }
+
+    /// Records that `source` is about to be moved into the trash as described by `log`, by `kind`.
+    pub fn begin(source: &Path, log: &TrashLog, kind: MoveKind) -> Result<JournalEntry, Error> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let id = format!(
+            "{}-{}",
+            OpId::current(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        Self::append(&JournalRecord::Pending {
+            id: id.clone(),
+            source: source.to_path_buf(),
+            log: Box::new(log.clone()),
+            kind,
+        })?;
+        Ok(JournalEntry { id })
+    }
+
+    /// Returns the moves which were neither committed nor aborted, oldest first.
+    pub fn unfinished() -> Result<Vec<Unfinished>, Error> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut order = Vec::new();
+        let mut entries: HashMap<String, Unfinished> = HashMap::new();
+        for (number, line) in BufReader::new(File::open(&path)?).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<JournalRecord>(&line) {
+                Ok(JournalRecord::Pending {
+                    id,
+                    source,
+                    log,
+                    kind,
+                }) => {
+                    order.push(id.clone());
+                    entries.insert(
+                        id.clone(),
+                        Unfinished {
+                            id,
+                            source,
+                            log: *log,
+                            kind,
+                            stage: JournalStage::Pending,
+                        },
+                    );
+                }
+                Ok(JournalRecord::Copied { id }) => {
+                    if let Some(entry) = entries.get_mut(&id) {
+                        entry.stage = JournalStage::Copied;
+                    }
+                }
+                Ok(JournalRecord::Done { id } | JournalRecord::Aborted { id }) => {
+                    entries.remove(&id);
+                }
+                // a partially written last line, its move never started
+                Err(err) => warn!(
+                    "{}:{}: skipping invalid journal record: {}",
+                    path.display(),
+                    number + 1,
+                    err
+                ),
+            }
+        }
+        Ok(order
+            .into_iter()
+            .filter_map(|id| entries.remove(&id))
+            .collect())
+    }
+
+    /// Whether the journal has anything in it, without reading it.
+    pub fn is_empty() -> bool {
+        Self::path()
+            .and_then(|path| Ok(path.metadata()?.len()))
+            .map_or(true, |len| len == 0)
+    }
+
+    /// Records that the interrupted move with the id `id` was recovered.
+    pub fn recovered(id: &str) -> Result<(), Error> {
+        Self::append(&JournalRecord::Done { id: id.to_string() })
+    }
+
+    /// Empties the journal, once every entry in it is finished or recovered.
+    pub fn clear() -> Result<(), Error> {
+        let file = File::create(Self::path()?)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    fn append(record: &JournalRecord) -> Result<(), Error> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::path()?)?;
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+impl Unfinished {
+    /// Whether the process which began the move is still running, it may be copying the item
+    /// without holding the lock. Entries are begun with the [`OpId`] of their process in their id.
+    pub fn in_progress(&self) -> bool {
+        self.id
+            .split('-')
+            .nth(1)
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .is_some_and(|pid| pid != std::process::id() && process_exists(pid))
+    }
+}
+
+impl JournalEntry {
+    /// Records that the item was copied into the trash and the copy verified.
+    pub fn copied(&self) -> Result<(), Error> {
+        Journal::append(&JournalRecord::Copied {
+            id: self.id.clone(),
+        })
+    }
+
+    /// Records that the move is in the history.
+    pub fn commit(self) -> Result<(), Error> {
+        Journal::append(&JournalRecord::Done { id: self.id })
+    }
+
+    /// Records that the move failed without changing anything.
+    pub fn abort(self) -> Result<(), Error> {
+        Journal::append(&JournalRecord::Aborted { id: self.id })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::LogId;
+
+    #[test]
+    fn record_format() {
+        let log = TrashLog::new(
+            LogId::from(20241112214434_u64),
+            PathBuf::from("/tmp/a.txt"),
+            PathBuf::from("/tmp/Trash/files/a.txt"),
+        );
+        let record = JournalRecord::Pending {
+            id: "1".to_string(),
+            source: PathBuf::from("/tmp/a.txt"),
+            log: Box::new(log),
+            kind: MoveKind::Rename,
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(line.starts_with(r#"{"state":"pending","id":"1""#));
+        assert!(line.ends_with(r#""kind":"rename"}"#));
+        assert_eq!(
+            serde_json::from_str::<JournalRecord>(&line).unwrap(),
+            record
+        );
+        // written by an older version
+        let old = line.replace(r#","kind":"rename""#, "");
+        assert!(matches!(
+            serde_json::from_str::<JournalRecord>(&old).unwrap(),
+            JournalRecord::Pending {
+                kind: MoveKind::Copy,
+                ..
+            }
+        ));
+        assert_eq!(
+            serde_json::to_string(&JournalRecord::Done { id: "1".into() }).unwrap(),
+            r#"{"state":"done","id":"1"}"#
+        );
+    }
+}
@@ -18,6 +18,14 @@ mod log;
 pub use log::*;
 mod errors;
 pub use errors::*;
+mod trash;
+pub use trash::*;
+mod lock;
+pub use lock::*;
+mod journal;
+pub use journal::*;
+mod meta;
+pub use meta::*;
 
 /// Splits the given `&Path` into directory path (prefix) and file name (suffix).
 ///
@@ -68,6 +76,62 @@ pub mod time {
     pub fn now() -> chrono::DateTime<chrono::Local> {
         chrono::Local::now()
     }
+
+    /// Parses a point in time given on the command line.
+    ///
+    /// Accepts a date (`2024-11-12`), a date and time (`2024-11-12 21:44` or `2024-11-12T21:44:34`),
+    /// RFC 3339 (`2024-11-12T21:44:34+05:30`), or a duration ago (`2d`, `3h 30min`, `1week`).
+    /// A bare date is the start of that day, or its end if `end_of_day` is set,
+    /// so `--until 2024-11-12` includes the whole day.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use roxide::time;
+    ///
+    /// let since = time::parse("2024-11-12", false).unwrap();
+    /// assert_eq!(since.format("%Y-%m-%d %H:%M").to_string(), "2024-11-12 00:00");
+    /// assert!(time::parse("2d", false).unwrap() < time::now());
+    /// ```
+    pub fn parse(
+        input: &str,
+        end_of_day: bool,
+    ) -> Result<chrono::DateTime<chrono::Local>, anyhow::Error> {
+        use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone};
+
+        let input = input.trim();
+        let local = |naive: NaiveDateTime| {
+            Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .ok_or_else(|| anyhow::anyhow!("`{}` doesn't exist in the local timezone", input))
+        };
+        if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+            return Ok(time.with_timezone(&Local));
+        }
+        for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+            if let Ok(naive) = NaiveDateTime::parse_from_str(input, format) {
+                return local(naive);
+            }
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+            let date = match end_of_day {
+                true => date.succ_opt().unwrap_or(date),
+                false => date,
+            };
+            return local(date.and_time(chrono::NaiveTime::MIN));
+        }
+        match humantime::parse_duration(input) {
+            Ok(ago) => TimeDelta::from_std(ago)
+                .ok()
+                .and_then(|ago| now().checked_sub_signed(ago))
+                .ok_or_else(|| anyhow::anyhow!("`{}` is too long ago", input)),
+            Err(_) => Err(anyhow::anyhow!(
+                "invalid time `{}`, expected eg: 2024-11-12, \"2024-11-12 21:44\" or 2d",
+                input
+            )),
+        }
+    }
 }
 
 ///# Returns the path to the user's local trash directory.
@@ -107,15 +171,18 @@ pub mod filter {
         }
 
         fn is_hidden(entry: &DirEntry) -> bool {
-            entry
-                .file_name()
-                .to_str()
-                .map(|s| s.starts_with("."))
-                .unwrap_or(false)
+            // the directory given as argument is walked even if it's hidden, eg: `.`
+            entry.depth() > 0
+                && entry
+                    .file_name()
+                    .to_str()
+                    .map(|s| s.starts_with("."))
+                    .unwrap_or(false)
         }
 
         fn check_exists(item: PathBuf) -> bool {
-            item.exists() || {
+            // symlink_metadata, so dangling symlinks can be removed too
+            item.symlink_metadata().is_ok() || {
                 // its OR not a colosure
                 show_error!(
                     "cannot remove '{}': no such file or directory",
@@ -136,25 +203,21 @@ pub mod filter {
                 if Self::check_exists(item.into()) {
                     match (args.recursive, args.pattern.is_some()) {
                         (true, true) => {
-                            for entry in item {
-                                let walker = WalkDir::new(entry).into_iter();
-                                for entry in
-                                    walker.into_iter().filter_entry(|e| !Self::is_hidden(e))
-                                {
-                                    let entry = entry?;
-                                    if entry.path().is_dir() {
-                                        continue;
-                                    }
-                                    entry
-                                        .path()
-                                        .file_name()
-                                        .map(|filename| {
-                                            if Self::matches_pattern(args, filename) {
-                                                files.push(entry.path().to_path_buf())
-                                            }
-                                        })
-                                        .ok_or(anyhow::anyhow!("fdd"))?;
+                            let walker = WalkDir::new(item).into_iter();
+                            for entry in walker.filter_entry(|e| !Self::is_hidden(e)) {
+                                let entry = entry?;
+                                if entry.path().is_dir() {
+                                    continue;
                                 }
+                                entry
+                                    .path()
+                                    .file_name()
+                                    .map(|filename| {
+                                        if Self::matches_pattern(args, filename) {
+                                            files.push(entry.path().to_path_buf())
+                                        }
+                                    })
+                                    .ok_or(anyhow::anyhow!("fdd"))?;
                             }
                         }
                         (true, false) => files.push(item.to_path_buf()),
@@ -188,7 +251,7 @@ pub mod filter {
                             }
                         }
                         (false, false) => {
-                            if item.is_file() || args.dir {
+                            if !item.is_dir() || item.is_symlink() || args.dir {
                                 files.push(item.to_path_buf())
                             } else {
                                 show_error!("cannot remove '{}': Is a directory", item.display());
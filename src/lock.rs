@@ -0,0 +1,200 @@
+use std::{
+    cell::Cell,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{Read, Seek, Write},
+    os::unix::fs::MetadataExt,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{debug, warn};
+
+use crate::{LocalDataDir, RoxError};
+
+/// Name of the lock file in the data dir.
+const LOCK_FILE: &str = "roxide.lock";
+/// Environment variable overriding [`DEFAULT_TIMEOUT`], in seconds.
+pub const LOCK_TIMEOUT_ENV: &str = "ROXIDE_LOCK_TIMEOUT";
+/// How long to wait for another roxide process to release the lock.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the lock has to be held for a dead process before it is considered stale.
+const STALE_AFTER: Duration = Duration::from_secs(1);
+const RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+thread_local! {
+    /// Number of [`StateLock`]s alive on this thread.
+    static HELD: Cell<usize> = const { Cell::new(0) };
+}
+
+/// # StateLock advisory lock on the history and the trash dirs
+///
+/// Everything which changes the history file or the contents of a trash dir, like allocating a
+/// trash name, reverting or cleaning up, holds this lock, so concurrent roxide processes
+/// can't interleave their changes. It is an exclusive `flock` on `$XDG_DATA_HOME/roxide/roxide.lock`,
+/// released when the `StateLock` is dropped or the process exits.
+///
+/// The lock is reentrant on the same thread: acquiring it again while it is held only
+/// returns another guard, the lock is released when the outermost guard is dropped.
+///
+/// The lock file contains the process id of its holder. If the lock is held but that process
+/// doesn't exist anymore, the lock is stale (its file descriptor leaked into another process)
+/// and the lock file is replaced.
+#[derive(Debug)]
+pub struct StateLock {
+    /// `None` for guards nested in an outer one.
+    file: Option<File>,
+}
+
+impl StateLock {
+    /// Acquires the lock, waiting up to `$ROXIDE_LOCK_TIMEOUT` seconds (10 by default)
+    /// for another process to release it.
+    pub fn acquire() -> Result<StateLock, RoxError> {
+        let timeout = std::env::var(LOCK_TIMEOUT_ENV)
+            .ok()
+            .and_then(|secs| secs.parse::<f64>().ok())
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+            .unwrap_or(DEFAULT_TIMEOUT);
+        let path = LocalDataDir::new()?.join(LOCK_FILE);
+        Self::acquire_at(&path, timeout)
+    }
+
+    /// Acquires the lock on the lock file at `path`.
+    pub fn acquire_at(path: &Path, timeout: Duration) -> Result<StateLock, RoxError> {
+        if HELD.get() > 0 {
+            HELD.set(HELD.get() + 1);
+            return Ok(StateLock { file: None });
+        }
+        let start = Instant::now();
+        // holder pid of the lock file, and since when it's known to be dead
+        let mut dead_holder: Option<(u32, Instant)> = None;
+        loop {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)?;
+            match file.try_lock() {
+                Ok(()) => {
+                    // the file may have been replaced as stale while we were waiting for it
+                    if !same_file(&file, path) {
+                        continue;
+                    }
+                    file.set_len(0)?;
+                    file.rewind()?;
+                    write!(file, "{}", std::process::id())?;
+                    HELD.set(1);
+                    debug!("acquired lock {}", path.display());
+                    return Ok(StateLock { file: Some(file) });
+                }
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(err)) => return Err(err.into()),
+            }
+
+            let holder = read_holder(&mut file);
+            match (holder, dead_holder) {
+                (Some(pid), Some((dead, since))) if pid == dead => {
+                    if since.elapsed() >= STALE_AFTER && same_file(&file, path) {
+                        warn!(
+                            "removing stale lock {}, its holder {} doesn't exist",
+                            path.display(),
+                            pid
+                        );
+                        fs::remove_file(path)?;
+                        dead_holder = None;
+                        continue;
+                    }
+                }
+                (Some(pid), _) if !process_exists(pid) => {
+                    dead_holder = Some((pid, Instant::now()));
+                }
+                _ => dead_holder = None,
+            }
+
+            if start.elapsed() >= timeout {
+                return Err(RoxError::Locked {
+                    path: path.to_path_buf(),
+                    holder: holder
+                        .map(|pid| format!("process {}", pid))
+                        .unwrap_or_else(|| "another process".to_string()),
+                });
+            }
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        HELD.set(HELD.get().saturating_sub(1));
+        if let Some(file) = &self.file {
+            // closing the file releases the lock anyway
+            let _ = file.unlock();
+        }
+    }
+}
+
+/// Whether the open `file` is still the file at `path`.
+fn same_file(file: &File, path: &Path) -> bool {
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+fn read_holder(file: &mut File) -> Option<u32> {
+    let mut content = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut content).ok()?;
+    content.trim().parse().ok()
+}
+
+pub(crate) fn process_exists(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn lock_is_reentrant() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(LOCK_FILE);
+        let outer = StateLock::acquire_at(&path, Duration::ZERO).unwrap();
+        let inner = StateLock::acquire_at(&path, Duration::ZERO).unwrap();
+        assert!(inner.file.is_none());
+        drop(inner);
+
+        // other threads open the file again, so they are blocked like other processes
+        let blocked = thread::spawn({
+            let path = path.clone();
+            move || StateLock::acquire_at(&path, Duration::ZERO).is_err()
+        });
+        assert!(blocked.join().unwrap());
+        drop(outer);
+        assert!(StateLock::acquire_at(&path, Duration::ZERO).is_ok());
+    }
+
+    #[test]
+    fn stale_lock_is_replaced() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join(LOCK_FILE);
+
+        // a lock held on behalf of a process which exited
+        let mut exited = Command::new("true").spawn().unwrap();
+        exited.wait().unwrap();
+        let mut leaked = File::create(&path).unwrap();
+        leaked.lock().unwrap();
+        write!(leaked, "{}", exited.id()).unwrap();
+
+        let lock = thread::spawn(move || {
+            StateLock::acquire_at(&path, STALE_AFTER * 3).map(|lock| lock.file.is_some())
+        });
+        assert!(lock.join().unwrap().unwrap());
+    }
+}
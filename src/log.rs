@@ -1,17 +1,45 @@
 use std::{
-    fs::{create_dir_all, File},
-    path::PathBuf,
+    fmt,
+    fs::{self, create_dir_all, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    num::ParseIntError,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
 };
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
 use dirs::data_dir;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 
+use crate::{ItemMeta, LogStatus, StateLock};
+
+/// Version of the records written to the history file.
+///
+/// Every record carries the version it was written with, so older records can still be read
+/// after the format changes.
+///
+/// - 1: first JSON Lines version
+/// - 2: added `op`
+/// - 3: added `status`, `reverted_at` and `restored_to`
+/// - 4: added `relative_path`
+/// - 5: added the `restored` and `purged` status
+/// - 6: added `item`
+/// - 7: added `atime`, `xattrs` and `parents` to `item`
+/// - 8: added `pinned`
+pub const HISTORY_VERSION: u32 = 8;
+
+/// Name of the line based history file used before the JSON Lines store.
+const LEGACY_LOG_FILE: &str = "history.log";
+const LEGACY_SEPARATOR: &str = "----------------------------";
+
 /// # LogId unique id which represents year, month, date, hour, minute and second
 /// in this order itself.
 ///
 /// LogId("%Y%m%d%H%M%S")
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct LogId(u64);
 
 impl From<i32> for LogId {
@@ -25,16 +53,239 @@ impl From<u64> for LogId {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl FromStr for LogId {
+    type Err = ParseIntError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim().parse().map(LogId)
+    }
+}
+
+impl fmt::Display for LogId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl LogId {
+    /// The local time this id represents.
+    pub fn to_time(self) -> Option<DateTime<FixedOffset>> {
+        let naive = NaiveDateTime::parse_from_str(&self.0.to_string(), "%Y%m%d%H%M%S").ok()?;
+        Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|time| time.fixed_offset())
+    }
+}
+
+/// # OpId unique id of a single roxide invocation
+///
+/// Every item trashed by one invocation shares the same OpId, so they can be reverted together.
+/// It is the time the invocation started ("%Y%m%d%H%M%S") followed by its process id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OpId(String);
+
+impl OpId {
+    /// The OpId of the current invocation.
+    pub fn current() -> &'static OpId {
+        static CURRENT: OnceLock<OpId> = OnceLock::new();
+        CURRENT.get_or_init(|| {
+            OpId(format!(
+                "{}-{}",
+                Local::now().format("%Y%m%d%H%M%S"),
+                std::process::id()
+            ))
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Records written before operation ids existed are grouped by their LogId.
+impl From<LogId> for OpId {
+    fn from(value: LogId) -> Self {
+        OpId(value.to_string())
+    }
+}
+
+impl From<&str> for OpId {
+    fn from(value: &str) -> Self {
+        OpId(value.to_string())
+    }
+}
+
+impl fmt::Display for OpId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct TrashMeta {
+    pub file_path: PathBuf,
+    pub trash_path: PathBuf,
+    /// The item as it was before it was trashed, `None` for records written before it was kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<ItemMeta>,
+}
+
+impl TrashMeta {
+    pub fn new(file_path: PathBuf, trash_path: PathBuf) -> Self {
+        Self {
+            file_path,
+            trash_path,
+            item: None,
+        }
+    }
+}
+
+impl fmt::Display for LogStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            LogStatus::Trashed => "trashed",
+            LogStatus::Reverted => "reverted",
+            LogStatus::Restored => "restored",
+            LogStatus::Purged => "purged",
+        };
+        f.pad(status)
+    }
+}
+
+/// A single record of the history file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TrashLog {
-    id: LogId,
-    from: PathBuf,
-    to: PathBuf,
+    pub version: u32,
+    pub id: LogId,
+    /// The invocation which trashed the item.
+    #[serde(default)]
+    pub op: OpId,
+    /// When the item was trashed, with the timezone it was trashed in.
+    pub time: DateTime<FixedOffset>,
+    #[serde(flatten)]
+    pub metadata: TrashMeta,
+    /// For items removed by a pattern, their path relative to the directory the pattern was
+    /// matched in, so the directory structure below it can be rebuilt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_path: Option<PathBuf>,
+    #[serde(default)]
+    pub status: LogStatus,
+    /// When the item was reverted, orders the redo stack.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverted_at: Option<DateTime<FixedOffset>>,
+    /// Where the item was reverted to, if that isn't its original path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restored_to: Option<PathBuf>,
+    /// Pinned items are never removed from the trash by `gc` or to keep it under its size limit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct LogFile {
-    logs: Vec<TrashLog>,
+impl TrashLog {
+    pub fn new(id: LogId, from: PathBuf, to: PathBuf) -> Self {
+        Self {
+            version: HISTORY_VERSION,
+            id,
+            op: OpId::current().clone(),
+            time: Local::now().fixed_offset(),
+            metadata: TrashMeta::new(from, to),
+            relative_path: None,
+            status: LogStatus::Trashed,
+            reverted_at: None,
+            restored_to: None,
+            pinned: false,
+        }
+    }
+
+    /// Marks the item as reverted to `path`, which is where redo picks it up again.
+    pub fn reverted(mut self, path: &Path) -> Self {
+        self.status = LogStatus::Reverted;
+        self.reverted_at = Some(Local::now().fixed_offset());
+        self.restored_to = (path != self.from()).then(|| path.to_path_buf());
+        self
+    }
+
+    /// Marks the item as restored to `path`.
+    pub fn restored(mut self, path: &Path) -> Self {
+        self.status = LogStatus::Restored;
+        self.restored_to = (path != self.from()).then(|| path.to_path_buf());
+        self
+    }
+
+    /// Marks the item as removed from the trash dir for good.
+    pub fn purged(mut self) -> Self {
+        self.status = LogStatus::Purged;
+        self
+    }
+
+    /// Marks the item as trashed again, now at `trash_path`.
+    pub fn retrashed(mut self, trash_path: PathBuf) -> Self {
+        self.status = LogStatus::Trashed;
+        self.reverted_at = None;
+        self.restored_to = None;
+        self.metadata.trash_path = trash_path;
+        self
+    }
+
+    /// Whether `other` is the same record, maybe in another state.
+    pub fn same_record(&self, other: &TrashLog) -> bool {
+        self.op == other.op && self.time == other.time && self.from() == other.from()
+    }
+
+    pub fn is_trashed(&self) -> bool {
+        self.status == LogStatus::Trashed
+    }
+
+    pub fn is_reverted(&self) -> bool {
+        self.status == LogStatus::Reverted
+    }
+
+    /// The status of the item as it is now, a trashed item which is no longer
+    /// in the trash dir was purged by someone else.
+    pub fn current_status(&self) -> LogStatus {
+        match self.status {
+            LogStatus::Trashed if self.to().symlink_metadata().is_err() => LogStatus::Purged,
+            status => status,
+        }
+    }
+
+    /// Where the item is outside of the trash dir after it was reverted.
+    pub fn reverted_path(&self) -> &PathBuf {
+        self.restored_to.as_ref().unwrap_or(self.from())
+    }
+
+    /// Brings a record written by an older version up to date.
+    fn upgrade(mut self) -> Self {
+        if self.version < 2 && self.op.is_empty() {
+            self.op = OpId::from(self.id);
+        }
+        self.version = HISTORY_VERSION;
+        self
+    }
+
+    pub fn id(&self) -> &LogId {
+        &self.id
+    }
+
+    pub fn op(&self) -> &OpId {
+        &self.op
+    }
+
+    pub fn from(&self) -> &PathBuf {
+        &self.metadata.file_path
+    }
+
+    pub fn to(&self) -> &PathBuf {
+        &self.metadata.trash_path
+    }
+
+    pub fn item(&self) -> Option<&ItemMeta> {
+        self.metadata.item.as_ref()
+    }
 }
 
 pub struct LocalDataDir(pub PathBuf);
@@ -53,132 +304,239 @@ impl LocalDataDir {
     }
 }
 
+/// The history file, `$XDG_DATA_HOME/roxide/history.jsonl`.
+///
+/// It is stored as JSON Lines, one [`TrashLog`] per line, so new records are appended
+/// without reading or rewriting the file. All reads and writes of the history go through here.
+/// A `history.log` written by older versions is migrated the first time the file is used.
 pub struct LocalLogFile(pub PathBuf);
 
 impl LocalLogFile {
     #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Result<PathBuf, Error> {
-        if let Some(log_dir) = data_dir() {
-            let log_dir = log_dir.join("roxide");
-            if !log_dir.exists() {
-                create_dir_all(&log_dir)?;
-            }
-            let log_file = log_dir.join("history.json");
-            if !log_file.exists() {
-                File::create(&log_file)?;
+        let log_dir = LocalDataDir::new()?;
+        let log_file = log_dir.join("history.jsonl");
+        let legacy_file = log_dir.join(LEGACY_LOG_FILE);
+        if legacy_file.exists() {
+            let _lock = StateLock::acquire()?;
+            // another process may have migrated it while we were waiting
+            if legacy_file.exists() {
+                Self::migrate(&legacy_file, &log_file)?;
             }
-            return Ok(log_file);
         }
-        Err(crate::RoxError::AnyError(anyhow::anyhow!("Failed to get Log file")).into())
+        if !log_file.exists() {
+            File::create(&log_file)?;
+        }
+        Ok(log_file)
     }
 
-    pub fn write(_log: TrashLog) -> Result<(), Error> {
+    /// Appends `log` to the history file.
+    pub fn write(log: &TrashLog) -> Result<(), Error> {
+        let _lock = StateLock::acquire()?;
         let log_file = LocalLogFile::new()?;
-        let mut _file = std::fs::OpenOptions::new().open(&log_file)?;
-        let con = std::fs::read_to_string(log_file)?;
-        let json: Vec<TrashLog> = serde_json::from_str(&con)?;
+        let mut file = OpenOptions::new()
+            .create(true) // Create the file if it doesn't exist
+            .append(true) // Append to the file if it already exists
+            .open(log_file)?;
+        writeln!(file, "{}", serde_json::to_string(log)?)?;
+        file.sync_data()?;
+        Ok(())
+    }
 
-        println!("{:#?}", json);
+    /// Reads every record of the history file, oldest first.
+    ///
+    /// Lines which can't be parsed, like a partially written last line, are skipped with a warning.
+    pub fn read() -> Result<Vec<TrashLog>, Error> {
+        let log_file = LocalLogFile::new()?;
+        let reader = BufReader::new(File::open(&log_file)?);
+        let mut logs = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<TrashLog>(&line) {
+                Ok(log) if log.version > HISTORY_VERSION => {
+                    return Err(anyhow!(
+                        "{}:{}: written by a newer version of roxide (history version {})",
+                        log_file.display(),
+                        number + 1,
+                        log.version
+                    ));
+                }
+                Ok(log) => logs.push(log.upgrade()),
+                Err(err) => warn!(
+                    "{}:{}: skipping invalid record: {}",
+                    log_file.display(),
+                    number + 1,
+                    err
+                ),
+            }
+        }
+        Ok(logs)
+    }
 
-        // writeln!(file, "{}", log.log_id.0)?;
-        // writeln!(file, "{}", log.metadata.file_path.to_string_lossy())?;
-        // writeln!(file, "{}", log.metadata.trash_path.to_string_lossy())?;
-        // writeln!(file, "----------------------------")?;
+    /// Replaces the contents of the history file with `logs`.
+    ///
+    /// The new contents are written to a temporary file which is then renamed over the history file,
+    /// so the history is never left half written.
+    ///
+    /// Callers which read the history before rewriting it should hold the [`StateLock`] since then.
+    pub fn rewrite(logs: &[TrashLog]) -> Result<(), Error> {
+        let _lock = StateLock::acquire()?;
+        let log_file = LocalLogFile::new()?;
+        Self::write_all(&log_file, logs)
+    }
 
+    fn write_all(log_file: &Path, logs: &[TrashLog]) -> Result<(), Error> {
+        let tmp_file = log_file.with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp_file)?;
+        for log in logs {
+            writeln!(file, "{}", serde_json::to_string(log)?)?;
+        }
+        file.sync_all()?;
+        fs::rename(tmp_file, log_file)?;
         Ok(())
     }
-}
-// pub fn write(history: History) -> anyhow::Result<(), Error> {
-//     let log_file = LocalLogFile::new()?;
-//     if !log_file.exists() {
-//         File::create(&log_file)?;
-//     }
-//     let mut file = OpenOptions::new()
-//         .create(true) // Create the file if it doesn't exist
-//         .append(true) // Append to the file if it already exists
-//         .open(log_file)?;
-//
-//     writeln!(file, "{}", history.log_id.0)?;
-//     writeln!(file, "{}", history.metadata.file_path.to_string_lossy())?;
-//     writeln!(file, "{}", history.metadata.trash_path.to_string_lossy())?;
-//     writeln!(file, "----------------------------")?;
-//     Ok(())
-// }
-//
-// #[allow(dead_code, unused)]
-// #[allow(clippy::unwrap_used)]
-// pub fn init_revert() -> Result<(), Error> {
-//     let log_dir = LocalDataDir::new()?;
-//     let log_file = log_dir.join("history.log");
-//     let file = fs::File::open(&log_file)?;
-//     let reader = BufReader::new(file);
-//
-//     let mut a_vec: Vec<String> = vec::Vec::new();
-//     for line in reader.lines() {
-//         let line = line?;
-//         a_vec.push(line);
-//     }
-//     Self::revert(
-//         a_vec.iter().nth_back(1).unwrap().to_string(),
-//         a_vec.iter().nth_back(2).unwrap().to_string(),
-//     )?;
-//
-//     let vec_lng = a_vec.len();
-//     debug!("vec len: {}", &vec_lng);
-//
-//     if vec_lng > 160 {
-//         a_vec.truncate(160);
-//     }
-//     let mut new_file = fs::File::create(log_file)?;
-//     for i in a_vec {
-//         writeln!(new_file, "{}", i)?;
-//     }
-//
-//     Ok(())
-// }
-// fn revert(from: String, to: String) -> Result<(), Error> {
-//     if !Path::new(&from).exists() {
-//         println!("File Doesn't Exist in Trash dir");
-//     } else {
-//         rename(from, to)?;
-//     }
-//     Ok(())
-// }
-// pub fn clean() {}
-//
-pub trait HistoryOps {
-    fn clean();
-    fn revert();
-    fn write();
-}
-
-#[allow(dead_code)]
-impl TrashLog {
-    pub fn new(id: LogId, from: PathBuf, to: PathBuf) -> Self {
-        Self { id, from, to }
-    }
 
-    pub fn set_id(&mut self, id: LogId) {
-        self.id = id;
+    /// Converts the line based `history.log` into records in front of the ones in `log_file`.
+    ///
+    /// The old file is kept as `history.log.migrated`.
+    fn migrate(legacy_file: &Path, log_file: &Path) -> Result<(), Error> {
+        let mut logs = parse_legacy(&fs::read_to_string(legacy_file)?);
+        debug!(
+            "migrating {} records from {}",
+            logs.len(),
+            legacy_file.display()
+        );
+        if log_file.exists() {
+            for line in fs::read_to_string(log_file)?.lines() {
+                if let Ok(log) = serde_json::from_str::<TrashLog>(line) {
+                    logs.push(log.upgrade());
+                }
+            }
+        }
+        Self::write_all(log_file, &logs)?;
+        fs::rename(legacy_file, legacy_file.with_extension("log.migrated"))?;
+        Ok(())
     }
+}
 
-    pub fn id(&self) -> &LogId {
-        &self.id
+/// Parses the blocks of the old `history.log`:
+///
+/// ```text
+/// 20241112214434
+/// /home/abhi/projects/abhi/github/roxide/file004.org
+/// /home/abhi/.local/share/Trash/files/file004.2024-11-12_21:44:34.org
+/// ----------------------------
+/// ```
+///
+/// Blocks which don't have exactly these three lines are skipped.
+pub fn parse_legacy(content: &str) -> Vec<TrashLog> {
+    let mut logs = Vec::new();
+    let mut block: Vec<&str> = Vec::with_capacity(3);
+    for line in content.lines() {
+        if line != LEGACY_SEPARATOR {
+            block.push(line);
+            continue;
+        }
+        match block.as_slice() {
+            [id, from, to] => {
+                let parsed = id.parse::<LogId>().ok();
+                match parsed.and_then(|log_id| log_id.to_time().map(|time| (log_id, time))) {
+                    Some((log_id, time)) => logs.push(TrashLog {
+                        time,
+                        op: OpId::from(log_id),
+                        ..TrashLog::new(log_id, PathBuf::from(from), PathBuf::from(to))
+                    }),
+                    None => warn!("skipping history record with invalid id: {}", id),
+                }
+            }
+            _ => warn!("skipping invalid history record: {:?}", block),
+        }
+        block.clear();
     }
+    logs
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
 
-    pub fn set_from(&mut self, from: PathBuf) {
-        self.from = from;
+    #[test]
+    fn parse_legacy_log() {
+        let dummy_log = [
+            "20241112214400",
+            "/home/abhi/projects/abhi/github/roxide/file003.org",
+            "/home/abhi/.local/share/Trash/files/file003.org",
+            "----------------------------",
+            "garbage",
+            "----------------------------",
+            "20241112214434",
+            "/home/abhi/projects/abhi/github/roxide/file004.org",
+            "/home/abhi/.local/share/Trash/files/file004.2024-11-12_21:44:34.org",
+            "----------------------------",
+        ]
+        .join("\n");
+        let logs = parse_legacy(&dummy_log);
+        assert_eq!(logs.len(), 2);
+        let last = logs.last().unwrap();
+        assert_eq!(last.id(), &LogId::from(20241112214434_u64));
+        assert_eq!(
+            last.from(),
+            Path::new("/home/abhi/projects/abhi/github/roxide/file004.org")
+        );
+        assert_eq!(
+            last.to(),
+            Path::new("/home/abhi/.local/share/Trash/files/file004.2024-11-12_21:44:34.org")
+        );
+        assert_eq!(
+            last.time.naive_local().to_string(),
+            "2024-11-12 21:44:34".to_string()
+        );
     }
 
-    pub fn from(&self) -> &PathBuf {
-        &self.from
+    #[test]
+    fn upgrade_v1_record() {
+        let line = r#"{"version":1,"id":20241112214434,"time":"2024-11-12T21:44:34+05:30","file_path":"/tmp/a","trash_path":"/tmp/Trash/files/a"}"#;
+        let log = serde_json::from_str::<TrashLog>(line).unwrap().upgrade();
+        assert_eq!(log.version, HISTORY_VERSION);
+        assert_eq!(log.op(), &OpId::from("20241112214434"));
+        assert_eq!(log.time.offset().local_minus_utc(), 5 * 3600 + 30 * 60);
+        assert!(log.is_trashed());
     }
 
-    pub fn set_to(&mut self, to: PathBuf) {
-        self.to = to;
+    #[test]
+    fn revert_and_retrash() {
+        let log = TrashLog::new(
+            LogId::from(20241112214434_u64),
+            PathBuf::from("/tmp/a.txt"),
+            PathBuf::from("/tmp/Trash/files/a.txt"),
+        );
+        let reverted = log.clone().reverted(Path::new("/tmp/a.txt"));
+        assert_eq!(reverted.status, LogStatus::Reverted);
+        assert_eq!(reverted.restored_to, None);
+        let line = serde_json::to_string(&reverted).unwrap();
+        assert!(line.contains(r#""status":"reverted""#));
+        assert_eq!(serde_json::from_str::<TrashLog>(&line).unwrap(), reverted);
+
+        let renamed = log.clone().reverted(Path::new("/tmp/a.restored.txt"));
+        assert_eq!(renamed.reverted_path(), Path::new("/tmp/a.restored.txt"));
+        assert_eq!(renamed.retrashed(log.to().clone()), log);
     }
 
-    pub fn to(&self) -> &PathBuf {
-        &self.to
+    #[test]
+    fn record_roundtrip() {
+        let log = TrashLog::new(
+            LogId::from(20241112214434_u64),
+            PathBuf::from("/tmp/new\nline.txt"),
+            PathBuf::from("/tmp/Trash/files/new\nline.txt"),
+        );
+        let line = serde_json::to_string(&log).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(serde_json::from_str::<TrashLog>(&line).unwrap(), log);
     }
 }
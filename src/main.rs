@@ -1,9 +1,24 @@
 use clap::Parser;
-use roxide::{Cli, Commands};
+use roxide::{show_error, Cli, Commands, ConfigFile};
 
 use std::{fs, path::Path};
 
-use self::core::{history::History, rm::init_remove};
+use self::core::{
+    diff::init_diff,
+    du::init_du,
+    find::init_find,
+    gc::{auto_gc, init_gc},
+    history::History,
+    list::init_list,
+    peek::{init_cat, init_ls},
+    pin::init_pin,
+    purge::init_purge,
+    query::init_history,
+    recover::recover,
+    restore::init_restore,
+    rm::init_remove,
+    tui::init_tui,
+};
 
 pub mod core;
 
@@ -11,8 +26,16 @@ fn main() {
     env_logger::init();
     let cli = Cli::parse();
 
+    if let Err(err) = recover() {
+        show_error!("failed to recover interrupted removals: {}", err);
+    }
+
     if let Some(items) = &cli.file {
         init_remove(items.to_vec(), &cli).unwrap();
+        if let Err(err) = ConfigFile::get_config().and_then(|config| auto_gc(&config, cli.verbose))
+        {
+            show_error!("automatic gc failed: {}", err);
+        }
     }
 
     if let Some(forece_file) = cli.force {
@@ -29,8 +52,104 @@ fn main() {
         }
     }
     match &cli.command {
-        Some(Commands::Revert) => {
-            History::init_revert().unwrap();
+        Some(Commands::Revert {
+            op,
+            on_conflict,
+            to,
+        }) => {
+            if let Err(err) = History::init_revert(op.as_deref(), *on_conflict, to.as_deref()) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Redo) => {
+            if let Err(err) = History::init_redo(cli.verbose) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Restore {
+            targets,
+            newest,
+            on_conflict,
+            to,
+        }) => {
+            if let Err(err) = init_restore(targets, *newest, *on_conflict, to.as_deref()) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::History(args)) => {
+            if let Err(err) = init_history(args) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::List(args)) => {
+            if let Err(err) = init_list(args) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Du(args)) => {
+            if let Err(err) = init_du(args) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Find(args)) => {
+            if let Err(err) = init_find(args) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Cat { targets, op }) => {
+            if let Err(err) = init_cat(targets, op.as_deref()) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Diff {
+            target,
+            op,
+            against,
+            context,
+        }) => {
+            if let Err(err) = init_diff(target, op.as_deref(), against.as_deref(), *context) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Ls {
+            target,
+            op,
+            recursive,
+        }) => {
+            if let Err(err) = init_ls(target, op.as_deref(), *recursive) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Tui { on_conflict }) => {
+            if let Err(err) = init_tui(*on_conflict) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Gc {
+            older_than,
+            dry_run,
+        }) => {
+            let gc = ConfigFile::get_config().and_then(|config| {
+                init_gc(older_than.as_deref(), &config, *dry_run, cli.verbose)
+            });
+            if let Err(err) = gc {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Purge { targets, op }) => {
+            if let Err(err) = init_purge(targets, op.as_deref()) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Pin { targets }) => {
+            if let Err(err) = init_pin(targets, true) {
+                show_error!("{}", err);
+            }
+        }
+        Some(Commands::Unpin { targets }) => {
+            if let Err(err) = init_pin(targets, false) {
+                show_error!("{}", err);
+            }
         }
         None => {}
     }
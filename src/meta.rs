@@ -0,0 +1,249 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    fs::{File, Metadata},
+    io,
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+/// Type of a trashed item.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    /// Sockets, fifos and device files
+    Other,
+}
+
+impl From<&Metadata> for FileKind {
+    fn from(meta: &Metadata) -> Self {
+        let file_type = meta.file_type();
+        if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else if file_type.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        }
+    }
+}
+
+/// # ItemMeta what an item was like when it was trashed
+///
+/// Captured from the item before it is moved and stored with its history record,
+/// so the trash can be listed, audited and checked for integrity, and the item restored
+/// as it was, without touching the trashed item itself.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct ItemMeta {
+    pub file_type: FileKind,
+    /// Size in bytes, the sum of every file in it for directories.
+    pub size: u64,
+    /// Permission bits, without the file type.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub mtime: DateTime<FixedOffset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub atime: Option<DateTime<FixedOffset>>,
+    /// Extended attributes of the item itself, hex encoded, see [`decode_hex`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub xattrs: BTreeMap<String, String>,
+    pub inode: u64,
+    pub device: u64,
+    /// Hex encoded sha256 of the contents, only for regular files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Working directory of the invocation which trashed the item.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// Command line of the invocation which trashed the item.
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// The directories the item was in, innermost first, so they can be recreated as they were.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<DirMeta>,
+}
+
+/// Attributes of a directory a trashed item was in.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, PartialOrd)]
+pub struct DirMeta {
+    pub path: PathBuf,
+    /// Permission bits, without the file type.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub mtime: DateTime<FixedOffset>,
+    pub atime: DateTime<FixedOffset>,
+    /// Hex encoded, see [`decode_hex`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub xattrs: BTreeMap<String, String>,
+}
+
+impl ItemMeta {
+    /// Reads the metadata of the item at `path`, without following symlinks.
+    ///
+    /// Regular files are read to hash their contents.
+    pub fn capture(path: &Path) -> io::Result<Self> {
+        let meta = path.symlink_metadata()?;
+        let file_type = FileKind::from(&meta);
+        Ok(Self {
+            file_type,
+            size: match file_type {
+                FileKind::Dir => item_size(path).unwrap_or(0),
+                _ => meta.len(),
+            },
+            mode: meta.mode() & 0o7777,
+            uid: meta.uid(),
+            gid: meta.gid(),
+            mtime: local_time(meta.modified()?),
+            atime: meta.accessed().ok().map(local_time),
+            xattrs: read_xattrs(path),
+            inode: meta.ino(),
+            device: meta.dev(),
+            sha256: match file_type {
+                FileKind::File => Some(to_hex(&hash_file(path)?)),
+                _ => None,
+            },
+            cwd: env::current_dir().ok(),
+            command: env::args_os()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            parents: path
+                .ancestors()
+                .skip(1)
+                .take_while(|dir| dir.parent().is_some())
+                .filter_map(|dir| DirMeta::capture(dir).ok())
+                .collect(),
+        })
+    }
+}
+
+impl DirMeta {
+    /// Reads the attributes of the directory at `path`.
+    pub fn capture(path: &Path) -> io::Result<Self> {
+        let meta = path.metadata()?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            mode: meta.mode() & 0o7777,
+            uid: meta.uid(),
+            gid: meta.gid(),
+            mtime: local_time(meta.modified()?),
+            atime: local_time(meta.accessed()?),
+            xattrs: read_xattrs(path),
+        })
+    }
+}
+
+/// Computes the sha256 hash of the contents of the file at `path`.
+pub fn hash_file(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Size of the item at `path` in bytes, the sum of every file in it for directories.
+///
+/// Symlinks are not followed. Returns `None` if `path` doesn't exist.
+pub fn item_size(path: &Path) -> Option<u64> {
+    path.symlink_metadata().ok()?;
+    Some(
+        WalkDir::new(path)
+            .into_iter()
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .filter(|meta| !meta.is_dir())
+            .map(|meta| meta.len())
+            .sum(),
+    )
+}
+
+/// Extended attributes of `path` with hex encoded values, empty if they can't be read.
+fn read_xattrs(path: &Path) -> BTreeMap<String, String> {
+    let Ok(names) = xattr::list(path) else {
+        return BTreeMap::new();
+    };
+    names
+        .filter_map(|name| {
+            let value = xattr::get(path, &name).ok()??;
+            Some((name.to_string_lossy().into_owned(), to_hex(&value)))
+        })
+        .collect()
+}
+
+fn local_time(time: SystemTime) -> DateTime<FixedOffset> {
+    DateTime::<Local>::from(time).fixed_offset()
+}
+
+/// Encodes `bytes` as lowercase hex, as sha256 digests and xattr values are recorded.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decodes a hex encoded xattr value, `None` if it isn't valid hex.
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::{fs, os::unix::fs::symlink};
+
+    use super::*;
+
+    #[test]
+    fn capture_item_meta() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+        fs::write(dir.join("b.txt"), "hello world").unwrap();
+        symlink("a.txt", dir.join("link")).unwrap();
+
+        let file = ItemMeta::capture(&dir.join("a.txt")).unwrap();
+        assert_eq!(file.file_type, FileKind::File);
+        assert_eq!(file.size, 5);
+        assert_eq!(
+            file.sha256.as_deref(),
+            Some("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+        );
+        assert_eq!(file.inode, dir.join("a.txt").metadata().unwrap().ino());
+        assert!(!file.command.is_empty());
+
+        let link = ItemMeta::capture(&dir.join("link")).unwrap();
+        assert_eq!(link.file_type, FileKind::Symlink);
+        assert_eq!(link.sha256, None);
+
+        let dir = ItemMeta::capture(&dir).unwrap();
+        assert_eq!(dir.file_type, FileKind::Dir);
+        // the symlink counts with the length of its target path
+        assert_eq!(dir.size, 5 + 11 + 5);
+        assert_eq!(dir.sha256, None);
+
+        assert_eq!(dir.parents[0].path, tmp.path());
+        assert_eq!(dir.parents.last().unwrap().path.parent(), Some(Path::new("/")));
+
+        assert_eq!(decode_hex(&to_hex(b"\x00user\xff")).unwrap(), b"\x00user\xff");
+        assert_eq!(decode_hex("abc"), None);
+
+        let line = serde_json::to_string(&dir).unwrap();
+        assert_eq!(serde_json::from_str::<ItemMeta>(&line).unwrap(), dir);
+    }
+}
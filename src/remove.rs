@@ -60,10 +60,11 @@ impl Remover {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum Intractive {
     Never,
     Once,
+    #[default]
     Always,
 }
 
@@ -92,9 +93,3 @@ impl Intractive {
         matches!(self, Self::Always)
     }
 }
-
-impl Default for Intractive {
-    fn default() -> Self {
-        Self::Always
-    }
-}
@@ -0,0 +1,415 @@
+use std::ffi::OsString;
+use std::fs::{self, create_dir_all, DirBuilder, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Error, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use log::debug;
+use percent_encoding::{percent_decode_str, percent_encode, AsciiSet, CONTROLS};
+
+use crate::current_uid;
+
+/// Characters escaped in the `Path` key of a `.trashinfo` file.
+///
+/// The FreeDesktop trash spec asks for the path to be "escaped in the same way as a URL",
+/// so everything except the unreserved set and the path separator is percent-encoded.
+const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'[')
+    .add(b'\\')
+    .add(b']')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
+const DELETION_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// The sticky bit, `S_ISVTX`.
+const STICKY_BIT: u32 = 0o1000;
+
+/// A FreeDesktop compliant trash directory.
+///
+/// `root` is the directory containing the `files` and `info` sub directories,
+/// eg: `$HOME/.local/share/Trash`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashDir {
+    pub root: PathBuf,
+}
+
+impl TrashDir {
+    /// The users home trash. ie, `$XDG_DATA_HOME/Trash`
+    pub fn home() -> Result<Self, Error> {
+        dirs::data_local_dir()
+            .map(|dir| Self {
+                root: dir.join("Trash"),
+            })
+            .ok_or(anyhow!("Failed to get local data dir"))
+    }
+
+    /// Finds the trash dir of the mount point `item` is located on.
+    ///
+    /// Used for items which are not on the same device as the home trash.
+    /// See [`TrashDir::in_top_dir`] for which directories are considered.
+    pub fn for_mount(item: &Path) -> Option<Self> {
+        let top_dir = mount_point(item)?;
+        let uid = current_uid()?;
+        Self::in_top_dir(&top_dir, uid)
+    }
+
+    /// Returns a usable trash dir in the top directory `top_dir` of a mount point.
+    ///
+    /// As described in the FreeDesktop trash spec, these are tried in order:
+    ///
+    /// 1. `$topdir/.Trash/$uid`: only if `$topdir/.Trash` is a directory (not a symlink)
+    ///    with the sticky bit set. It is created if it doesn't exist yet.
+    /// 2. `$topdir/.Trash-$uid`: created with mode `0700` if it doesn't exist yet,
+    ///    and used only if it is a directory (not a symlink) owned by the user.
+    pub fn in_top_dir(top_dir: &Path, uid: u32) -> Option<Self> {
+        let shared = top_dir.join(".Trash");
+        match fs::symlink_metadata(&shared) {
+            Ok(meta) if meta.is_dir() && meta.mode() & STICKY_BIT != 0 => {
+                let root = shared.join(uid.to_string());
+                if ensure_private_dir(&root, uid) {
+                    return Some(Self { root });
+                }
+            }
+            Ok(meta) if meta.file_type().is_symlink() => {
+                debug!("{} is a symlink, ignoring it", shared.display());
+            }
+            Ok(_) => debug!("{} has no sticky bit set, ignoring it", shared.display()),
+            Err(_) => {}
+        }
+        let root = top_dir.join(format!(".Trash-{}", uid));
+        ensure_private_dir(&root, uid).then_some(Self { root })
+    }
+
+    /// Returns the trash dir containing `trash_path`, which is an item inside its `files` directory.
+    pub fn containing(trash_path: &Path) -> Option<Self> {
+        trash_path
+            .parent()
+            .filter(|files| files.file_name().is_some_and(|name| name == "files"))
+            .and_then(Path::parent)
+            .map(|root| Self {
+                root: root.to_path_buf(),
+            })
+    }
+
+    pub fn files(&self) -> PathBuf {
+        self.root.join("files")
+    }
+
+    pub fn info(&self) -> PathBuf {
+        self.root.join("info")
+    }
+
+    /// Path of the `.trashinfo` file which belongs to the trashed item `name`.
+    pub fn info_path(&self, name: &str) -> PathBuf {
+        self.info().join(format!("{}.trashinfo", name))
+    }
+
+    /// Creates the `files` and `info` directories if they are missing.
+    pub fn create(&self) -> io::Result<()> {
+        create_dir_all(self.files())?;
+        create_dir_all(self.info())?;
+        Ok(())
+    }
+
+    /// The top directory of the mount point, for trash dirs of a mount point.
+    pub fn top_dir(&self) -> Option<&Path> {
+        let name = self.root.file_name()?.to_str()?;
+        let parent = self.root.parent()?;
+        if name.starts_with(".Trash-") {
+            Some(parent)
+        } else if parent.file_name().is_some_and(|name| name == ".Trash") {
+            parent.parent()
+        } else {
+            None
+        }
+    }
+
+    /// Reads every `.trashinfo` file of this trash dir.
+    ///
+    /// Returns the path of each trashed item in `files` along with its info.
+    /// Relative paths, which the spec allows in trash dirs of a mount point, are made absolute.
+    /// Info files which can't be parsed are skipped.
+    pub fn read_infos(&self) -> Vec<(PathBuf, TrashInfo)> {
+        let Ok(entries) = fs::read_dir(self.info()) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let info_path = entry.path();
+                let name = info_path.file_name()?.to_str()?.strip_suffix(".trashinfo")?;
+                let trash_path = self.files().join(name);
+                match TrashInfo::read(&info_path) {
+                    Ok(mut info) => {
+                        if info.path.is_relative() {
+                            info.path = self.top_dir()?.join(&info.path);
+                        }
+                        Some((trash_path, info))
+                    }
+                    Err(err) => {
+                        debug!("{}", err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Whether `name` is free in both `files` and `info`.
+    pub fn is_free(&self, name: &str) -> bool {
+        self.files().join(name).symlink_metadata().is_err()
+            && self.info_path(name).symlink_metadata().is_err()
+    }
+}
+
+/// Creates `dir` with mode `0700` if it doesn't exist.
+///
+/// Returns true only if `dir` is a real directory (not a symlink) owned by `uid`.
+fn ensure_private_dir(dir: &Path, uid: u32) -> bool {
+    if let Err(err) = DirBuilder::new().mode(0o700).create(dir) {
+        if err.kind() != io::ErrorKind::AlreadyExists {
+            debug!("can't create {}: {}", dir.display(), err);
+            return false;
+        }
+    }
+    fs::symlink_metadata(dir).is_ok_and(|meta| meta.is_dir() && meta.uid() == uid)
+}
+
+/// Returns the top directory of the mount point containing `item`.
+///
+/// Walks up from the parent of `item` until the device changes.
+pub fn mount_point(item: &Path) -> Option<PathBuf> {
+    let parent = fs::canonicalize(item.parent()?).ok()?;
+    let dev = fs::metadata(&parent).ok()?.dev();
+    let mut top_dir = parent.as_path();
+    while let Some(ancestor) = top_dir.parent() {
+        match fs::metadata(ancestor) {
+            Ok(meta) if meta.dev() == dev => top_dir = ancestor,
+            _ => break,
+        }
+    }
+    Some(top_dir.to_path_buf())
+}
+
+/// Total size in bytes of the filesystem containing `path`.
+pub fn filesystem_size(path: &Path) -> io::Result<u64> {
+    let stat = rustix::fs::statvfs(path)?;
+    Ok(stat.f_blocks.saturating_mul(stat.f_frsize))
+}
+
+/// Contents of a `.trashinfo` file.
+///
+/// ```text
+/// [Trash Info]
+/// Path=/home/alice/some%20file.txt
+/// DeletionDate=2024-11-12T21:44:34
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashInfo {
+    pub path: PathBuf,
+    pub deletion_date: NaiveDateTime,
+}
+
+impl TrashInfo {
+    pub fn new(path: PathBuf, deletion_date: DateTime<Local>) -> Self {
+        Self {
+            path,
+            deletion_date: deletion_date.naive_local(),
+        }
+    }
+
+    /// Writes the info file for `name` into the `info` directory of `trash`.
+    ///
+    /// The file is created exclusively, so a name that is already taken by another
+    /// trash implementation results in an `AlreadyExists` error instead of being overwritten.
+    pub fn write(&self, trash: &TrashDir, name: &str) -> io::Result<PathBuf> {
+        let info_path = trash.info_path(name);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)?;
+        file.write_all(self.to_string().as_bytes())?;
+        Ok(info_path)
+    }
+
+    pub fn read(info_path: &Path) -> Result<Self, Error> {
+        Self::parse(&fs::read_to_string(info_path)?)
+            .ok_or(anyhow!("invalid trashinfo file: `{}`", info_path.display()))
+    }
+
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut lines = content.lines().map(str::trim);
+        if lines.next() != Some("[Trash Info]") {
+            return None;
+        }
+        let mut path = None;
+        let mut deletion_date = None;
+        for line in lines {
+            if line.starts_with('[') {
+                break;
+            }
+            match line.split_once('=') {
+                Some(("Path", value)) => path = Some(decode_path(value)),
+                Some(("DeletionDate", value)) => {
+                    deletion_date = NaiveDateTime::parse_from_str(value, DELETION_DATE_FORMAT).ok()
+                }
+                _ => {}
+            }
+        }
+        Some(Self {
+            path: path?,
+            deletion_date: deletion_date?,
+        })
+    }
+
+    pub fn deletion_date_local(&self) -> Option<DateTime<Local>> {
+        Local.from_local_datetime(&self.deletion_date).earliest()
+    }
+}
+
+impl std::fmt::Display for TrashInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[Trash Info]")?;
+        writeln!(f, "Path={}", encode_path(&self.path))?;
+        writeln!(
+            f,
+            "DeletionDate={}",
+            self.deletion_date.format(DELETION_DATE_FORMAT)
+        )
+    }
+}
+
+fn encode_path(path: &Path) -> String {
+    percent_encode(path.as_os_str().as_bytes(), PATH_ENCODE_SET).to_string()
+}
+
+fn decode_path(value: &str) -> PathBuf {
+    PathBuf::from(OsString::from_vec(percent_decode_str(value).collect()))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+    use std::path::{Path, PathBuf};
+
+    use chrono::NaiveDate;
+
+    use super::{mount_point, TrashDir, TrashInfo};
+    use crate::current_uid;
+
+    #[test]
+    fn trashinfo_roundtrip() {
+        let info = TrashInfo {
+            path: PathBuf::from("/home/alice/my dir/100% [done]?.txt"),
+            deletion_date: NaiveDate::from_ymd_opt(2024, 11, 12)
+                .unwrap()
+                .and_hms_opt(21, 44, 34)
+                .unwrap(),
+        };
+        let content = info.to_string();
+        assert_eq!(
+            content,
+            "[Trash Info]\nPath=/home/alice/my%20dir/100%25%20%5Bdone%5D%3F.txt\nDeletionDate=2024-11-12T21:44:34\n"
+        );
+        assert_eq!(TrashInfo::parse(&content).unwrap(), info);
+    }
+
+    #[test]
+    fn trashinfo_rejects_missing_header() {
+        assert!(TrashInfo::parse("Path=/tmp/a\nDeletionDate=2024-11-12T21:44:34\n").is_none());
+    }
+
+    #[test]
+    fn trash_dir_containing() {
+        let trash = TrashDir::containing(Path::new("/home/alice/.local/share/Trash/files/a.txt"));
+        assert_eq!(
+            trash.unwrap().info_path("a.txt"),
+            Path::new("/home/alice/.local/share/Trash/info/a.txt.trashinfo")
+        );
+        assert!(TrashDir::containing(Path::new("/tmp/a.txt")).is_none());
+    }
+
+    #[test]
+    fn trash_dir_top_dir() {
+        let top_dir = |root: &str| {
+            TrashDir {
+                root: PathBuf::from(root),
+            }
+            .top_dir()
+            .map(Path::to_path_buf)
+        };
+        assert_eq!(top_dir("/media/usb/.Trash-1000").unwrap(), Path::new("/media/usb"));
+        assert_eq!(top_dir("/media/usb/.Trash/1000").unwrap(), Path::new("/media/usb"));
+        assert!(top_dir("/home/alice/.local/share/Trash").is_none());
+    }
+
+    #[test]
+    fn top_dir_trash_prefers_sticky_shared_trash() {
+        let top_dir = tempfile::tempdir().unwrap();
+        let uid = current_uid().unwrap();
+        let shared = top_dir.path().join(".Trash");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+
+        let trash = TrashDir::in_top_dir(top_dir.path(), uid).unwrap();
+        assert_eq!(trash.root, shared.join(uid.to_string()));
+        assert!(trash.root.is_dir());
+    }
+
+    #[test]
+    fn top_dir_trash_rejects_unsafe_shared_trash() {
+        let top_dir = tempfile::tempdir().unwrap();
+        let uid = current_uid().unwrap();
+        let shared = top_dir.path().join(".Trash");
+
+        // no sticky bit
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o777)).unwrap();
+        let trash = TrashDir::in_top_dir(top_dir.path(), uid).unwrap();
+        assert_eq!(trash.root, top_dir.path().join(format!(".Trash-{}", uid)));
+        assert_eq!(
+            fs::metadata(&trash.root).unwrap().permissions().mode() & 0o777,
+            0o700
+        );
+
+        // symlink to a sticky directory
+        fs::remove_dir(&shared).unwrap();
+        let target = top_dir.path().join("elsewhere");
+        fs::create_dir(&target).unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o1777)).unwrap();
+        symlink(&target, &shared).unwrap();
+        let trash = TrashDir::in_top_dir(top_dir.path(), uid).unwrap();
+        assert_eq!(trash.root, top_dir.path().join(format!(".Trash-{}", uid)));
+    }
+
+    #[test]
+    fn top_dir_trash_rejects_symlinked_user_trash() {
+        let top_dir = tempfile::tempdir().unwrap();
+        let uid = current_uid().unwrap();
+        let target = top_dir.path().join("elsewhere");
+        fs::create_dir(&target).unwrap();
+        symlink(&target, top_dir.path().join(format!(".Trash-{}", uid))).unwrap();
+        assert!(TrashDir::in_top_dir(top_dir.path(), uid).is_none());
+    }
+
+    #[test]
+    fn mount_point_of_root() {
+        assert_eq!(mount_point(Path::new("/bin")).unwrap(), Path::new("/"));
+    }
+}
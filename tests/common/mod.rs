@@ -0,0 +1,32 @@
+//! Helpers shared by the integration tests, not every test uses all of them.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use roxide::TrashLog;
+
+/// Runs roxide with `home` as its HOME, so it uses its own trash and data dir.
+pub fn roxide(home: &Path) -> Command {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_roxide"));
+    cmd.env("HOME", home)
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("XDG_CONFIG_HOME")
+        .current_dir(home);
+    cmd
+}
+
+/// The history file of roxide running with `home` as its HOME.
+pub fn history_file(home: &Path) -> PathBuf {
+    home.join(".local/share/roxide/history.jsonl")
+}
+
+/// Every record of the history file, oldest first.
+pub fn read_history(home: &Path) -> Vec<TrashLog> {
+    fs::read_to_string(history_file(home))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::Path;
+
+use roxide::TrashLog;
+
+mod common;
+
+use common::roxide;
+
+fn diff(home: &Path, args: &[&str]) -> String {
+    let output = roxide(home).arg("diff").args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(output.stderr.is_empty(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Trashes `name` with `contents`, returns the id of the operation.
+fn trash(home: &Path, name: &str, contents: &[u8]) -> String {
+    fs::write(home.join(name), contents).unwrap();
+    assert!(roxide(home).arg(name).status().unwrap().success());
+    let history = fs::read_to_string(home.join(".local/share/roxide/history.jsonl")).unwrap();
+    let last: TrashLog = serde_json::from_str(history.lines().last().unwrap()).unwrap();
+    last.op().to_string()
+}
+
+#[test]
+fn diff_trashed_versions() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let first = trash(home, "notes.txt", b"one\ntwo\nthree\n");
+    let second = trash(home, "notes.txt", b"one\n2\nthree\n");
+    fs::write(home.join("notes.txt"), "one\n2\nthree\nfour\n").unwrap();
+
+    let output = diff(home, &["notes.txt"]);
+    assert!(output.starts_with("--- "), "{}", output);
+    assert!(output.contains("(trashed "), "{}", output);
+    assert!(output.contains("\n+four\n"), "{}", output);
+    assert!(!output.contains("-two"), "{}", output);
+
+    let output = diff(home, &["notes.txt", "--op", &first]);
+    assert!(output.contains("\n-two\n+2\n"), "{}", output);
+    assert!(output.contains("\n+four\n"), "{}", output);
+
+    let output = diff(home, &["notes.txt", "--op", &first, "--against", &second]);
+    assert!(output.contains("\n-two\n+2\n"), "{}", output);
+    assert!(!output.contains("four"), "{}", output);
+
+    fs::write(home.join("notes.txt"), "one\n2\nthree\n").unwrap();
+    assert_eq!(diff(home, &["notes.txt"]), "");
+}
+
+#[test]
+fn diff_binary_files() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    trash(home, "image.bin", b"\x00\x01\x02");
+    fs::write(home.join("image.bin"), b"\x00\x01\x02\x03").unwrap();
+    let output = diff(home, &["image.bin"]);
+    assert!(output.starts_with("binary files differ\n"), "{}", output);
+    assert_eq!(output.matches("sha256 ").count(), 2, "{}", output);
+    assert!(
+        output.contains("3 B") && output.contains("4 B"),
+        "{}",
+        output
+    );
+
+    fs::write(home.join("image.bin"), b"\x00\x01\x02").unwrap();
+    assert_eq!(diff(home, &["image.bin"]), "");
+}
@@ -0,0 +1,81 @@
+use std::fs;
+
+use serde_json::Value;
+
+mod common;
+
+use common::roxide;
+
+/// The `name` and `bytes` of the groups of a breakdown.
+fn usage(report: &Value, breakdown: &str) -> Vec<(String, u64)> {
+    report[breakdown]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|usage| {
+            (
+                usage["name"].as_str().unwrap().to_string(),
+                usage["bytes"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn du_breaks_down_trash_size() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::create_dir_all(home.join("docs/old")).unwrap();
+    fs::write(home.join("docs/report.PDF"), "0123456789").unwrap();
+    fs::write(home.join("docs/old/notes.txt"), "01234").unwrap();
+    fs::write(home.join("todo.txt"), "012").unwrap();
+    fs::create_dir(home.join("build")).unwrap();
+    fs::write(home.join("build/out.o"), "0123456789012345678901234").unwrap();
+    for item in ["docs/report.PDF", "docs/old/notes.txt", "todo.txt"] {
+        assert!(roxide(home).arg(item).status().unwrap().success());
+    }
+    assert!(roxide(home)
+        .args(["-r", "build"])
+        .status()
+        .unwrap()
+        .success());
+
+    let output = roxide(home)
+        .args(["du", "--format", "json", "--top", "2"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["items"], 4);
+    assert_eq!(report["bytes"], 43);
+    assert_eq!(
+        usage(&report, "by_dir"),
+        [("~".to_string(), 28), ("~/docs".to_string(), 15)]
+    );
+    assert_eq!(
+        usage(&report, "by_extension"),
+        [
+            ("(dir)".to_string(), 25),
+            ("pdf".to_string(), 10),
+            ("txt".to_string(), 8)
+        ]
+    );
+    assert_eq!(
+        usage(&report, "by_age"),
+        [("less than a day".to_string(), 43)]
+    );
+    assert_eq!(usage(&report, "by_op").len(), 4);
+    let largest: Vec<&str> = report["largest"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["original_path"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        largest,
+        [
+            home.join("build").to_str().unwrap(),
+            home.join("docs/report.PDF").to_str().unwrap()
+        ]
+    );
+}
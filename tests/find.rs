@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+mod common;
+
+use common::roxide;
+
+/// The original paths of the files `roxide find` prints, relative to `home`.
+fn find(home: &Path, args: &[&str]) -> Vec<String> {
+    let output = roxide(home)
+        .args(["find", "--format", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let rows: Vec<Value> = serde_json::from_slice(&output.stdout).unwrap();
+    rows.iter()
+        .map(|row| {
+            let original = Path::new(row["original_path"].as_str().unwrap());
+            let trash_path = Path::new(row["trash_path"].as_str().unwrap());
+            assert!(trash_path.exists());
+            original.strip_prefix(home).unwrap().display().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn find_in_trash() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::create_dir_all(home.join("project/src")).unwrap();
+    fs::write(home.join("project/src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(
+        home.join("project/src/lib.rs"),
+        "pub fn big() {}\n".repeat(100),
+    )
+    .unwrap();
+    fs::write(home.join("project/README.md"), "# project\n").unwrap();
+    fs::write(home.join("main.rs"), "fn main() { todo!() }\n").unwrap();
+    assert!(roxide(home)
+        .args(["-r", "project"])
+        .status()
+        .unwrap()
+        .success());
+    // trashed twice, the second one gets another name in the trash dir
+    assert!(roxide(home).arg("main.rs").status().unwrap().success());
+    fs::write(home.join("main.rs"), "fn main() {}\n").unwrap();
+    assert!(roxide(home).arg("main.rs").status().unwrap().success());
+
+    assert_eq!(
+        find(home, &["--name", "*.rs"]),
+        [
+            "project/src/lib.rs",
+            "project/src/main.rs",
+            "main.rs",
+            "main.rs"
+        ]
+    );
+    assert_eq!(find(home, &["--regex", "^src$"]), ["project/src"]);
+    assert_eq!(
+        find(home, &["--name", "main.rs", "--dir", "project"]),
+        ["project/src/main.rs"]
+    );
+    assert_eq!(find(home, &["--min-size", "1K"]), ["project/src/lib.rs"]);
+    assert_eq!(find(home, &["--contains", r"todo!\(\)"]), ["main.rs"]);
+    assert!(find(home, &["--since", "1d", "--until", "2d"]).is_empty());
+
+    let output = roxide(home)
+        .args(["find", "--name", "README.md"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let columns: Vec<&str> = stdout.trim_end().split('\t').collect();
+    let [item, original, trash_path] = columns[..] else {
+        panic!("{}", stdout);
+    };
+    assert_eq!(Path::new(item), home.join("project"));
+    assert_eq!(Path::new(original), home.join("project/README.md"));
+    assert!(trash_path.ends_with("/Trash/files/project/README.md"));
+}
@@ -1,7 +1,179 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::DateTime;
+use filetime::FileTime;
+use roxide::LogStatus;
+
+mod common;
+
+use common::{history_file, read_history, roxide};
+
+/// Trashes `name` in `home`, as if it was trashed in 2020 if `old` is set.
+fn trash(home: &Path, name: &str, old: bool) -> PathBuf {
+    let item = home.join(name);
+    fs::write(&item, name).unwrap();
+    assert!(roxide(home).arg(&item).status().unwrap().success());
+    if old {
+        let lines: Vec<String> = read_history(home)
+            .into_iter()
+            .map(|mut log| {
+                if log.from() == &item {
+                    log.time = DateTime::parse_from_rfc3339("2020-01-01T00:00:00+00:00").unwrap();
+                }
+                serde_json::to_string(&log).unwrap()
+            })
+            .collect();
+        fs::write(history_file(home), lines.join("\n") + "\n").unwrap();
+    }
+    home.join(".local/share/Trash/files").join(name)
+}
+
 #[test]
-fn test_gc() {    
-    // TODO: actual test
-    let result = true;
-    // always true
-    assert!(result)
-}
\ No newline at end of file
+fn gc_purges_old_items() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let old = trash(home, "old.txt", true);
+    let new = trash(home, "new.txt", false);
+    // trashed by another tool
+    let other = home.join(".local/share/Trash/files/other.txt");
+    fs::write(&other, "other").unwrap();
+    fs::write(
+        home.join(".local/share/Trash/info/other.txt.trashinfo"),
+        "[Trash Info]\nPath=/somewhere/other.txt\nDeletionDate=2020-01-01T00:00:00\n",
+    )
+    .unwrap();
+
+    let output = roxide(home)
+        .args(["gc", "30d", "--dry-run"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("would purge 2 items"), "{}", stdout);
+    assert!(old.exists() && other.exists());
+
+    assert!(roxide(home).args(["gc", "30d"]).status().unwrap().success());
+    assert!(!old.exists());
+    assert!(!other.exists());
+    assert!(!home
+        .join(".local/share/Trash/info/old.txt.trashinfo")
+        .exists());
+    assert!(new.exists());
+    let logs = read_history(home);
+    assert_eq!(logs[0].status, LogStatus::Purged);
+    assert_eq!(logs[1].status, LogStatus::Trashed);
+}
+
+#[test]
+fn gc_runs_after_remove_when_configured() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let config = home.join(".config/roxide");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(
+        config.join("config.toml"),
+        "[settings]\nnew_check_sha256 = false\ngc_after = \"30d\"\n",
+    )
+    .unwrap();
+
+    let first = trash(home, "first.txt", true);
+    let stamp = home.join(".local/share/roxide/gc.stamp");
+    assert!(stamp.exists());
+    // the last gc was two days ago, so the next remove runs it again
+    let two_days_ago = FileTime::from_unix_time(FileTime::now().unix_seconds() - 2 * 86400, 0);
+    filetime::set_file_mtime(&stamp, two_days_ago).unwrap();
+    let second = trash(home, "second.txt", true);
+    assert!(!first.exists());
+    assert!(second.exists());
+
+    // it ran less than a day ago
+    trash(home, "third.txt", false);
+    assert!(second.exists());
+}
+
+#[test]
+fn quota_evicts_oldest_unpinned_items() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let config = home.join(".config/roxide");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(
+        config.join("config.toml"),
+        "[settings]\nnew_check_sha256 = false\nmax_trash_size = \"20\"\n",
+    )
+    .unwrap();
+
+    let files = home.join(".local/share/Trash/files");
+    let trash = |name: &str| {
+        fs::write(home.join(name), "0123456789").unwrap();
+        roxide(home).arg(name).output().unwrap()
+    };
+    trash("a.txt");
+    let status = roxide(home).args(["pin", "a.txt"]).status().unwrap();
+    assert!(status.success());
+    trash("b.txt");
+    assert!(files.join("b.txt").exists());
+
+    let output = trash("c.txt");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("evicted"), "{}", stdout);
+    assert!(files.join("a.txt").exists());
+    assert!(!files.join("b.txt").exists());
+    assert!(files.join("c.txt").exists());
+    let logs = read_history(home);
+    assert!(logs[0].pinned);
+    assert_eq!(logs[1].status, LogStatus::Purged);
+}
+
+#[test]
+fn quota_keeps_the_item_just_trashed() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let config = home.join(".config/roxide");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(
+        config.join("config.toml"),
+        "[settings]\nnew_check_sha256 = false\nmax_trash_size = \"20\"\n",
+    )
+    .unwrap();
+
+    let files = home.join(".local/share/Trash/files");
+    fs::write(home.join("small.txt"), "0123456789").unwrap();
+    assert!(roxide(home).arg("small.txt").status().unwrap().success());
+    fs::write(home.join("big.txt"), "0123456789".repeat(3)).unwrap();
+    let output = roxide(home).arg("big.txt").output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("evicted `"), "{}", stdout);
+    assert!(stdout.contains("alone is over the limit"), "{}", stdout);
+    assert!(!files.join("small.txt").exists());
+    assert!(files.join("big.txt").exists());
+    let logs = read_history(home);
+    assert_eq!(logs[0].status, LogStatus::Purged);
+    assert_eq!(logs[1].status, LogStatus::Trashed);
+}
+
+#[test]
+fn quota_is_only_enforced_after_trashing() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let config = home.join(".config/roxide");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(
+        config.join("config.toml"),
+        "[settings]\nnew_check_sha256 = false\n",
+    )
+    .unwrap();
+
+    let trashed = trash(home, "a.txt", false);
+    fs::write(
+        config.join("config.toml"),
+        "[settings]\nnew_check_sha256 = false\nmax_trash_size = \"1\"\n",
+    )
+    .unwrap();
+    // nothing is trashed, so nothing is evicted
+    let output = roxide(home).arg("missing.txt").output().unwrap();
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("evicted"));
+    assert!(trashed.exists());
+    assert_eq!(read_history(home)[0].status, LogStatus::Trashed);
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use roxide::{JournalRecord, LogId, MoveKind, TrashLog};
+
+mod common;
+
+use common::roxide;
+
+/// A pending journal entry of moving `home/name` to the trash by `kind`.
+fn pending(home: &Path, name: &str, kind: MoveKind) -> (PathBuf, TrashLog, JournalRecord) {
+    let source = home.join(name);
+    let log = TrashLog::new(
+        LogId::from(20241112214434_u64),
+        source.clone(),
+        home.join(".local/share/Trash/files").join(name),
+    );
+    let record = JournalRecord::Pending {
+        id: name.to_string(),
+        source: source.clone(),
+        log: Box::new(log.clone()),
+        kind,
+    };
+    (source, log, record)
+}
+
+fn info_path(home: &Path, name: &str) -> PathBuf {
+    home.join(".local/share/Trash/info")
+        .join(format!("{}.trashinfo", name))
+}
+
+#[test]
+fn interrupted_moves_are_recovered() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let data_dir = home.join(".local/share/roxide");
+    fs::create_dir_all(&data_dir).unwrap();
+    fs::create_dir_all(home.join(".local/share/Trash/files")).unwrap();
+    fs::create_dir_all(home.join(".local/share/Trash/info")).unwrap();
+    let mut journal = Vec::new();
+
+    // renamed into the trash, killed before the history was written
+    let (moved, moved_log, record) = pending(home, "moved.txt", MoveKind::Rename);
+    fs::write(moved_log.to(), "moved").unwrap();
+    fs::write(info_path(home, "moved.txt"), "").unwrap();
+    journal.push(record);
+
+    // renamed into the trash, then something new was created at the same path
+    let (recreated, recreated_log, record) = pending(home, "recreated.txt", MoveKind::Rename);
+    fs::write(recreated_log.to(), "old").unwrap();
+    fs::write(info_path(home, "recreated.txt"), "").unwrap();
+    fs::write(&recreated, "new").unwrap();
+    journal.push(record);
+
+    // killed after writing the trashinfo file, before the rename
+    let (unmoved, _, record) = pending(home, "unmoved.txt", MoveKind::Rename);
+    fs::write(&unmoved, "unmoved").unwrap();
+    fs::write(info_path(home, "unmoved.txt"), "").unwrap();
+    journal.push(record);
+
+    // killed while copying
+    let (partial, partial_log, record) = pending(home, "partial", MoveKind::Copy);
+    fs::create_dir_all(partial.join("sub")).unwrap();
+    fs::write(partial.join("sub/file"), "complete").unwrap();
+    fs::create_dir_all(partial_log.to()).unwrap();
+    fs::write(info_path(home, "partial"), "").unwrap();
+    journal.push(record);
+
+    // killed while removing the original of a verified copy
+    let (copied, copied_log, record) = pending(home, "copied", MoveKind::Copy);
+    fs::create_dir_all(&copied).unwrap();
+    fs::create_dir_all(copied_log.to().join("sub")).unwrap();
+    fs::write(copied_log.to().join("sub/file"), "complete").unwrap();
+    fs::write(info_path(home, "copied"), "").unwrap();
+    journal.push(record);
+    journal.push(JournalRecord::Copied {
+        id: "copied".to_string(),
+    });
+
+    // finished normally
+    let (_, _, record) = pending(home, "done.txt", MoveKind::Rename);
+    journal.push(record);
+    journal.push(JournalRecord::Done {
+        id: "done.txt".to_string(),
+    });
+
+    let mut lines: Vec<String> = journal
+        .iter()
+        .map(|record| serde_json::to_string(record).unwrap())
+        .collect();
+    // a line which was only partially written when the power went out
+    lines.push(r#"{"state":"pending","id":"torn","sou"#.to_string());
+    fs::write(data_dir.join("journal.jsonl"), lines.join("\n")).unwrap();
+
+    let output = roxide(home).output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    assert!(!moved.exists() && moved_log.to().exists());
+    assert_eq!(fs::read_to_string(&recreated).unwrap(), "new");
+    assert_eq!(fs::read_to_string(recreated_log.to()).unwrap(), "old");
+    assert!(info_path(home, "recreated.txt").exists());
+    assert!(unmoved.exists() && !info_path(home, "unmoved.txt").exists());
+    assert!(partial.join("sub/file").exists());
+    assert!(!partial_log.to().exists() && !info_path(home, "partial").exists());
+    assert!(!copied.exists() && copied_log.to().join("sub/file").exists());
+
+    let history: Vec<TrashLog> = fs::read_to_string(data_dir.join("history.jsonl"))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(history, vec![moved_log, recreated_log, copied_log]);
+    assert_eq!(
+        fs::read_to_string(data_dir.join("journal.jsonl")).unwrap(),
+        ""
+    );
+}
+
+#[test]
+fn moves_in_progress_are_left_alone() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let data_dir = home.join(".local/share/roxide");
+    fs::create_dir_all(&data_dir).unwrap();
+    fs::create_dir_all(home.join(".local/share/Trash/files")).unwrap();
+    fs::create_dir_all(home.join(".local/share/Trash/info")).unwrap();
+
+    // still being copied by this process, which doesn't hold the lock while copying
+    let (source, log, _) = pending(home, "big", MoveKind::Copy);
+    fs::create_dir_all(&source).unwrap();
+    fs::create_dir_all(log.to()).unwrap();
+    fs::write(info_path(home, "big"), "").unwrap();
+    let record = JournalRecord::Pending {
+        id: format!("20241112214434-{}-0", std::process::id()),
+        source: source.clone(),
+        log: Box::new(log.clone()),
+        kind: MoveKind::Copy,
+    };
+    let journal = serde_json::to_string(&record).unwrap() + "\n";
+    fs::write(data_dir.join("journal.jsonl"), &journal).unwrap();
+
+    let output = roxide(home).output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(source.exists() && log.to().exists());
+    assert!(info_path(home, "big").exists());
+    assert_eq!(
+        fs::read_to_string(data_dir.join("journal.jsonl")).unwrap(),
+        journal
+    );
+}
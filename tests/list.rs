@@ -0,0 +1,60 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+mod common;
+
+use common::roxide;
+
+fn list(home: &Path, args: &[&str]) -> Vec<Value> {
+    let output = roxide(home)
+        .args(["list", "--format", "json"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn list_trash_with_orphans() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::create_dir(home.join("docs")).unwrap();
+    fs::write(home.join("docs/big.txt"), "a lot of contents").unwrap();
+    fs::write(home.join("small.txt"), "a").unwrap();
+    for item in ["docs/big.txt", "small.txt"] {
+        assert!(roxide(home).arg(item).status().unwrap().success());
+    }
+    let files = home.join(".local/share/Trash/files");
+    // trashed by another tool
+    fs::write(files.join("other.txt"), "other").unwrap();
+    fs::write(
+        home.join(".local/share/Trash/info/other.txt.trashinfo"),
+        "[Trash Info]\nPath=/somewhere/other.txt\nDeletionDate=2020-01-01T00:00:00\n",
+    )
+    .unwrap();
+    // without any metadata
+    fs::write(files.join("stray"), "").unwrap();
+
+    let rows = list(home, &["--sort", "size", "--reverse"]);
+    let names: Vec<&str> = rows
+        .iter()
+        .map(|row| row["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["big.txt", "other.txt", "small.txt", "stray"]);
+    assert_eq!(rows[0]["revertible"], true);
+    assert_eq!(rows[0]["size"], 17);
+    assert_eq!(rows[1]["revertible"], false);
+    assert_eq!(rows[1]["original_path"], "/somewhere/other.txt");
+    assert_eq!(rows[3]["orphan"], true);
+    assert_eq!(rows[3]["original_path"], Value::Null);
+
+    let rows = list(home, &["--dir", "docs"]);
+    assert_eq!(rows.len(), 1);
+    assert_eq!(
+        rows[0]["original_path"],
+        home.join("docs/big.txt").to_str().unwrap()
+    );
+}
@@ -1,7 +1,121 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use roxide::TrashLog;
+
+mod common;
+
+use common::{read_history, roxide};
+
+fn data_dir(home: &Path) -> PathBuf {
+    home.join(".local/share/roxide")
+}
+
 #[test]
-fn test_lock_file() {    
-    // TODO: actual test
-    let result = true;
-    // always true
-    assert!(result)
-}
\ No newline at end of file
+fn concurrent_removes_dont_collide() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    // every process trashes files with the same names in the same second
+    let workers = 8;
+    let files = 10;
+    let children: Vec<_> = (0..workers)
+        .map(|worker| {
+            let dir = home.join(format!("worker{}", worker));
+            fs::create_dir(&dir).unwrap();
+            let items: Vec<PathBuf> = (0..files)
+                .map(|n| {
+                    let item = dir.join(format!("file{}.txt", n));
+                    fs::write(&item, format!("{} {}", worker, n)).unwrap();
+                    item
+                })
+                .collect();
+            roxide(home).args(&items).spawn().unwrap()
+        })
+        .collect();
+    for child in children {
+        let output: Output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{:?}", output);
+    }
+
+    let logs = read_history(home);
+    assert_eq!(logs.len(), workers * files);
+    let trash_paths: HashSet<&PathBuf> = logs.iter().map(TrashLog::to).collect();
+    assert_eq!(trash_paths.len(), logs.len());
+    for log in &logs {
+        assert!(!log.from().exists());
+        let contents = fs::read_to_string(log.to()).unwrap();
+        assert!(log.from().to_string_lossy().contains(&format!(
+            "worker{}",
+            contents.split(' ').next().unwrap()
+        )));
+        let name = log.to().file_name().unwrap().to_string_lossy().to_string();
+        let info = home.join(format!(".local/share/Trash/info/{}.trashinfo", name));
+        assert!(info.exists());
+    }
+}
+
+#[test]
+fn concurrent_reverts_restore_everything_once() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let items: Vec<PathBuf> = (0..20)
+        .map(|n| {
+            let item = home.join(format!("file{}.txt", n));
+            fs::write(&item, "contents").unwrap();
+            item
+        })
+        .collect();
+    for item in &items {
+        let status = roxide(home).arg(item).status().unwrap();
+        assert!(status.success());
+    }
+
+    let children: Vec<_> = (0..items.len())
+        .map(|_| roxide(home).arg("revert").spawn().unwrap())
+        .collect();
+    for child in children {
+        assert!(child.wait_with_output().unwrap().status.success());
+    }
+    for item in &items {
+        assert!(item.exists());
+        assert!(!item.with_extension("restored.txt").exists());
+    }
+    assert!(read_history(home).iter().all(|log| !log.is_trashed()));
+}
+
+#[test]
+fn remove_waits_for_lock_and_times_out() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::create_dir_all(data_dir(home)).unwrap();
+    let mut holder = File::create(data_dir(home).join("roxide.lock")).unwrap();
+    holder.lock().unwrap();
+    write!(holder, "{}", std::process::id()).unwrap();
+
+    let item = home.join("file.txt");
+    fs::write(&item, "contents").unwrap();
+    let output = roxide(home)
+        .env("ROXIDE_LOCK_TIMEOUT", "0.5")
+        .arg(&item)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(item.exists());
+    assert!(
+        stderr.contains(&format!("locked by process {}", std::process::id())),
+        "{}",
+        stderr
+    );
+
+    holder.unlock().unwrap();
+    let output = roxide(home)
+        .env("ROXIDE_LOCK_TIMEOUT", "0.5")
+        .arg(&item)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!item.exists());
+}
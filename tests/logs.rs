@@ -1,7 +1,36 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use roxide::{LocalDataDir, LocalLogFile, LogId, TrashLog};
+
 #[test]
-fn test_log_apis() {    
-    // TODO: actual test
-    let result = true;
-    // always true
-    assert!(result)
-}
\ No newline at end of file
+fn test_log_apis() {
+    let home = tempfile::tempdir().unwrap();
+    std::env::set_var("HOME", home.path());
+    std::env::remove_var("XDG_DATA_HOME");
+
+    // history written by older versions is migrated on first use
+    let data_dir = LocalDataDir::new().unwrap();
+    fs::write(
+        data_dir.join("history.log"),
+        "20241112214434\n/home/abhi/file004.org\n/home/abhi/.local/share/Trash/files/file004.org\n----------------------------\n",
+    )
+    .unwrap();
+
+    let log = TrashLog::new(
+        LogId::from(20241112214500_u64),
+        PathBuf::from("/home/abhi/new\nline.txt"),
+        PathBuf::from("/home/abhi/.local/share/Trash/files/new\nline.txt"),
+    );
+    LocalLogFile::write(&log).unwrap();
+
+    let logs = LocalLogFile::read().unwrap();
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0].from(), Path::new("/home/abhi/file004.org"));
+    assert_eq!(logs[1], log);
+    assert!(!data_dir.join("history.log").exists());
+    assert!(data_dir.join("history.log.migrated").exists());
+
+    LocalLogFile::rewrite(&logs[1..]).unwrap();
+    assert_eq!(LocalLogFile::read().unwrap(), vec![log]);
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+use roxide::TrashLog;
+
+mod common;
+
+use common::roxide;
+
+fn run(home: &Path, args: &[&str]) -> Output {
+    let output = roxide(home).args(args).output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    output
+}
+
+fn stdout(home: &Path, args: &[&str]) -> String {
+    String::from_utf8(run(home, args).stdout).unwrap()
+}
+
+#[test]
+fn cat_and_ls_trashed_items() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::create_dir_all(home.join("project/src")).unwrap();
+    fs::write(home.join("project/src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(home.join("project/README.md"), "# project\n").unwrap();
+    run(home, &["-r", "project"]);
+    fs::write(home.join("notes.txt"), "first\n").unwrap();
+    run(home, &["notes.txt"]);
+    fs::write(home.join("notes.txt"), "second\n").unwrap();
+    run(home, &["notes.txt"]);
+
+    assert_eq!(stdout(home, &["cat", "notes.txt"]), "second\n");
+    assert_eq!(
+        stdout(home, &["cat", "project/src/main.rs", "project/README.md"]),
+        "fn main() {}\n# project\n"
+    );
+    // by the name in the trash dir, the second `notes.txt` got another one
+    let renamed: Vec<String> = fs::read_dir(home.join(".local/share/Trash/files"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("notes") && name != "notes.txt")
+        .collect();
+    assert_eq!(renamed.len(), 1);
+    assert_eq!(stdout(home, &["cat", &renamed[0]]), "second\n");
+    // the first one by its operation
+    let history = fs::read_to_string(home.join(".local/share/roxide/history.jsonl")).unwrap();
+    let first: TrashLog = history
+        .lines()
+        .map(|line| serde_json::from_str::<TrashLog>(line).unwrap())
+        .find(|log| log.from().ends_with("notes.txt"))
+        .unwrap();
+    assert_eq!(
+        stdout(home, &["cat", "notes.txt", "--op", first.op().as_str()]),
+        "first\n"
+    );
+
+    let output = run(home, &["cat", "project"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is a directory"));
+
+    let listing = stdout(home, &["ls", "project"]);
+    let names: Vec<&str> = listing
+        .lines()
+        .map(|line| line.rsplit("  ").next().unwrap())
+        .collect();
+    assert_eq!(names, ["README.md", "src/"]);
+    assert!(listing.starts_with('-'), "{}", listing);
+    let listing = stdout(home, &["ls", "-R", "project"]);
+    let names: Vec<&str> = listing
+        .lines()
+        .map(|line| line.rsplit("  ").next().unwrap())
+        .collect();
+    assert_eq!(names, ["README.md", "src/", "src/main.rs"]);
+}
@@ -0,0 +1,57 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Output, Stdio};
+
+use roxide::LogStatus;
+
+mod common;
+
+use common::{read_history, roxide};
+
+/// Runs `roxide purge args` and answers its prompt with `answer`.
+fn purge(home: &Path, args: &[&str], answer: &str) -> Output {
+    let mut child = roxide(home)
+        .arg("purge")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    writeln!(child.stdin.take().unwrap(), "{}", answer).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn purge_selected_items() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    for name in ["a.txt", "b.txt", "c.log"] {
+        fs::write(home.join(name), name).unwrap();
+    }
+    assert!(roxide(home).arg("a.txt").status().unwrap().success());
+    assert!(roxide(home)
+        .args(["b.txt", "c.log"])
+        .status()
+        .unwrap()
+        .success());
+    let files = home.join(".local/share/Trash/files");
+    let info = home.join(".local/share/Trash/info");
+
+    let output = purge(home, &["a.txt"], "n");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("permanently delete 1 item?"));
+    assert!(files.join("a.txt").exists());
+
+    purge(home, &["a.txt"], "y");
+    assert!(!files.join("a.txt").exists());
+    assert!(!info.join("a.txt.trashinfo").exists());
+
+    let op = read_history(home)[1].op().to_string();
+    let output = purge(home, &["--op", &op[..10]], "y");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("purged 2 items"));
+    assert!(fs::read_dir(&files).unwrap().next().is_none());
+    assert!(fs::read_dir(&info).unwrap().next().is_none());
+    assert!(read_history(home)
+        .iter()
+        .all(|log| log.status == LogStatus::Purged));
+}
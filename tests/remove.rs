@@ -1,7 +1,190 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use roxide::LogStatus;
+
+mod common;
+
+use common::{read_history, roxide};
+
 #[test]
-fn test_core() {    
-    // TODO: actual test
-    let result = true;
-    // always true
-    assert!(result)
-}
\ No newline at end of file
+fn pattern_remove_is_revertible() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    let files: Vec<PathBuf> = ["docs/x.pdf", "docs/a/x.pdf", "docs/a/b/x.pdf"]
+        .iter()
+        .map(|file| home.join(file))
+        .collect();
+    for file in &files {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, file.to_string_lossy().as_bytes()).unwrap();
+    }
+    fs::write(home.join("docs/a/keep.txt"), "kept").unwrap();
+
+    let status = roxide(home).args(["docs", "-rp", ".pdf"]).status().unwrap();
+    assert!(status.success());
+    assert!(files.iter().all(|file| !file.exists()));
+    assert!(home.join("docs/a/keep.txt").exists());
+
+    let logs = read_history(home);
+    assert_eq!(logs.len(), files.len());
+    assert!(logs.iter().all(|log| log.op() == logs[0].op()));
+    let mut relative: Vec<PathBuf> = logs
+        .iter()
+        .map(|log| log.relative_path.clone().unwrap())
+        .collect();
+    relative.sort();
+    assert_eq!(
+        relative,
+        vec![
+            PathBuf::from("a/b/x.pdf"),
+            PathBuf::from("a/x.pdf"),
+            PathBuf::from("x.pdf")
+        ]
+    );
+
+    // the sub directories are gone too by the time of the revert
+    fs::remove_dir_all(home.join("docs/a")).unwrap();
+    let status = roxide(home).arg("revert").status().unwrap();
+    assert!(status.success());
+    for file in &files {
+        assert_eq!(fs::read_to_string(file).unwrap(), file.to_string_lossy());
+    }
+}
+
+#[test]
+fn revert_and_restore_elsewhere() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::create_dir_all(home.join("src/b")).unwrap();
+    fs::write(home.join("src/one.txt"), "old one").unwrap();
+    fs::write(home.join("src/b/two.txt"), "old two").unwrap();
+    let status = roxide(home)
+        .args(["src/one.txt", "src/b/two.txt"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    fs::write(home.join("src/one.txt"), "new one").unwrap();
+
+    let status = roxide(home)
+        .args(["revert", "--to", "old"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(home.join("src/one.txt")).unwrap(), "new one");
+    assert_eq!(fs::read_to_string(home.join("old/one.txt")).unwrap(), "old one");
+    assert_eq!(fs::read_to_string(home.join("old/b/two.txt")).unwrap(), "old two");
+
+    let status = roxide(home).arg("src/one.txt").status().unwrap();
+    assert!(status.success());
+    let status = roxide(home)
+        .args(["restore", "src/one.txt", "--to", "one.txt"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(home.join("one.txt")).unwrap(), "new one");
+    assert!(!home.join("src/one.txt").exists());
+    let logs = read_history(home);
+    assert_eq!(logs.last().unwrap().restored_to, Some(home.join("one.txt")));
+}
+
+#[test]
+fn revert_keeps_merged_and_missing_items() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::create_dir(home.join("dir")).unwrap();
+    fs::write(home.join("dir/old.txt"), "old").unwrap();
+    fs::write(home.join("gone.txt"), "gone").unwrap();
+    let status = roxide(home)
+        .args(["-r", "dir", "gone.txt"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    fs::create_dir(home.join("dir")).unwrap();
+    fs::write(home.join("dir/new.txt"), "new").unwrap();
+    let gone = read_history(home)
+        .into_iter()
+        .find(|log| log.from() == &home.join("gone.txt"))
+        .unwrap();
+    fs::remove_file(gone.to()).unwrap();
+
+    let status = roxide(home)
+        .args(["revert", "--on-conflict", "merge"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(home.join("dir/old.txt")).unwrap(), "old");
+    let statuses: Vec<(PathBuf, LogStatus)> = read_history(home)
+        .iter()
+        .map(|log| (log.from().clone(), log.current_status()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            (home.join("dir"), LogStatus::Restored),
+            (home.join("gone.txt"), LogStatus::Purged)
+        ]
+    );
+}
+
+#[test]
+fn removing_something_new_keeps_reverted_items() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    fs::write(home.join("one.txt"), "one").unwrap();
+    fs::write(home.join("two.txt"), "two").unwrap();
+    assert!(roxide(home).arg("one.txt").status().unwrap().success());
+    assert!(roxide(home).arg("revert").status().unwrap().success());
+    assert!(roxide(home).arg("two.txt").status().unwrap().success());
+
+    let statuses: Vec<(PathBuf, LogStatus)> = read_history(home)
+        .iter()
+        .map(|log| (log.from().clone(), log.current_status()))
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            (home.join("one.txt"), LogStatus::Restored),
+            (home.join("two.txt"), LogStatus::Trashed)
+        ]
+    );
+    assert!(roxide(home).arg("redo").status().unwrap().success());
+    assert!(home.join("one.txt").exists());
+}
+
+#[test]
+fn restore_asks_again_for_invalid_choices() {
+    let home = tempfile::tempdir().unwrap();
+    let home = home.path();
+    for contents in ["first", "second"] {
+        fs::write(home.join("n.txt"), contents).unwrap();
+        assert!(roxide(home).arg("n.txt").status().unwrap().success());
+    }
+
+    let mut child = roxide(home)
+        .args(["restore", "n.txt"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"00\n+0\n3\n1\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for choice in ["00", "+0", "3"] {
+        assert!(
+            stderr.contains(&format!("invalid choice: `{}`", choice)),
+            "{}",
+            stderr
+        );
+    }
+    assert!(home.join("n.txt").exists());
+}